@@ -3,7 +3,7 @@ fn main() {
         .nth(1)
         .expect("A .obj file to print is required");
 
-    let (models, materials) =
+    let (models, materials, _) =
         tobj::load_obj(obj_file, &tobj::LoadOptions::default()).expect("Failed to OBJ load file");
 
     // Note: If you don't mind missing the materials, you can generate a default.
@@ -9,9 +9,9 @@
 //!
 //! Meshes can be triangulated on the fly or left as-is.
 //!
-//! Only polygons that are trivially convertible to triangle fans are supported.
-//! Arbitrary polygons may not behave as expected. The best solution would be to
-//! convert your mesh to solely consist of triangles in your modeling software.
+//! By default polygons are split into triangle fans, which is only correct for
+//! convex polygons. Concave and non-planar polygons can be split by
+//! [ear clipping](Triangulation::EarClipping) instead.
 //!
 //! ## Optional – Normals & Texture Coordinates
 //!
@@ -19,7 +19,9 @@
 //! texture coordinates are optional.
 //!
 //! If no normals or texture coordinates are found then the corresponding
-//! [`Vec`](Mesh::normals)s for the [`Mesh`] will be empty.
+//! [`Vec`](Mesh::normals)s for the [`Mesh`] will be empty. Missing normals can
+//! be generated instead via the
+//! [`generate_normals`](LoadOptions::generate_normals) option.
 //!
 //! ## Flat Data
 //!
@@ -46,9 +48,18 @@
 //!
 //! ## Materials
 //!
-//! Standard `MTL` attributes are supported too. Any unrecognized parameters
-//! will be stored in a `HashMap` containing the key-value pairs of the
-//! unrecognized parameter and its value.
+//! Standard `MTL` attributes are supported too, as are the attributes of the
+//! widely used PBR extension (roughness, metallic, sheen, ...). Texture maps
+//! are parsed into a [`TextureMap`] holding the file name and any options given
+//! for it. Any unrecognized parameters will be stored in a `HashMap` containing
+//! the key-value pairs of the unrecognized parameter and its value.
+//!
+//! ## Writing
+//!
+//! Models can be written back to `OBJ` files via [`save_obj()`] or to any
+//! [`Write`](std::io::Write) implementation via [`write_obj_buf()`]. Likewise,
+//! materials can be written to `MTL` files via [`save_mtl()`] and
+//! [`write_mtl_buf()`].
 //!
 //! ## Example
 //!
@@ -67,7 +78,7 @@
 //! let cornell_box = tobj::load_obj("obj/cornell_box.obj", &tobj::GPU_LOAD_OPTIONS);
 //! assert!(cornell_box.is_ok());
 //!
-//! let (models, materials) = cornell_box.expect("Failed to load OBJ file");
+//! let (models, materials, _) = cornell_box.expect("Failed to load OBJ file");
 //!
 //! // Materials might report a separate loading error if the MTL file wasn't found.
 //! // If you don't need the materials, you can generate a default here and use that
@@ -228,8 +239,14 @@
 //!   using [tokio](https://crates.io/crates/tokio) [AsyncRead](::tokio::io::AsyncRead)
 //!   traits.
 //!
+//! * [`tangents`](LoadOptions::generate_tangents) – Adds support for generating
+//!   MikkTSpace tangents for normal mapping.
+//!
 //! * ['use_f64'] - Uses double-precision (f64) instead of single-precision
 //!   (f32) floating point types
+//!
+//! * [`mmap`](load_obj_mmap) – Adds support for loading obj files mapped into
+//!   memory using [memmap2](https://crates.io/crates/memmap2).
 #![cfg_attr(feature = "merging", allow(incomplete_features))]
 #![cfg_attr(feature = "merging", feature(generic_const_exprs))]
 
@@ -240,9 +257,10 @@ use std::{
     error::Error,
     fmt,
     fs::File,
-    io::{prelude::*, BufReader},
+    io::{prelude::*, BufReader, BufWriter},
     path::{Path, PathBuf},
     str::{FromStr, SplitWhitespace},
+    sync::Arc,
 };
 
 #[cfg(feature = "use_f64")]
@@ -265,8 +283,8 @@ type HashMap<K, V> = std::collections::HashMap<K, V>;
 
 /// Typical [`LoadOptions`] for using meshes in a GPU/relatime context.
 ///
-/// Faces are *triangulated*, a *single index* is generated and *degenerate
-/// faces* (points & lines) are *discarded*.
+/// Faces are *triangulated*, a *single index* is generated and *points* and
+/// *polylines* are *discarded*.
 pub const GPU_LOAD_OPTIONS: LoadOptions = LoadOptions {
     #[cfg(feature = "merging")]
     merge_identical_points: false,
@@ -274,16 +292,27 @@ pub const GPU_LOAD_OPTIONS: LoadOptions = LoadOptions {
     reorder_data: false,
     single_index: true,
     triangulate: true,
+    triangulation: Triangulation::Fan,
     ignore_points: true,
     ignore_lines: true,
+    per_face_materials: false,
+    generate_normals: NormalGeneration::Disabled,
+    #[cfg(feature = "tangents")]
+    generate_tangents: false,
+    skip_invalid_lines: false,
+    source_vertices: false,
+    source_faces: false,
+    tessellate_surfaces: None,
+    point_cloud: false,
+    preserve_w: false,
 };
 
 /// Typical [`LoadOptions`] for using meshes with an offline rendeder.
 ///
 /// Faces are *kept as they are* (e.g. n-gons) and *normal and texture
 /// coordinate data is reordered* so only a single index is needed.
-/// Topology remains unchanged except for *degenerate faces* (points & lines)
-/// which are *discarded*.
+/// Topology remains unchanged except for *points* and *polylines* which are
+/// *discarded*.
 pub const OFFLINE_RENDERING_LOAD_OPTIONS: LoadOptions = LoadOptions {
     #[cfg(feature = "merging")]
     merge_identical_points: true,
@@ -291,8 +320,19 @@ pub const OFFLINE_RENDERING_LOAD_OPTIONS: LoadOptions = LoadOptions {
     reorder_data: true,
     single_index: false,
     triangulate: false,
+    triangulation: Triangulation::Fan,
     ignore_points: true,
     ignore_lines: true,
+    per_face_materials: false,
+    generate_normals: NormalGeneration::Disabled,
+    #[cfg(feature = "tangents")]
+    generate_tangents: false,
+    skip_invalid_lines: false,
+    source_vertices: false,
+    source_faces: false,
+    tessellate_surfaces: None,
+    point_cloud: false,
+    preserve_w: false,
 };
 
 /// A mesh made up of triangles loaded from some `OBJ` file.
@@ -318,7 +358,7 @@ pub const OFFLINE_RENDERING_LOAD_OPTIONS: LoadOptions = LoadOptions {
 /// let cornell_box = tobj::load_obj("obj/cornell_box.obj", &tobj::GPU_LOAD_OPTIONS);
 /// assert!(cornell_box.is_ok());
 ///
-/// let (models, materials) = cornell_box.unwrap();
+/// let (models, materials, _) = cornell_box.unwrap();
 ///
 /// let mesh = &models[0].mesh;
 /// let i = mesh.indices[0] as usize;
@@ -349,24 +389,56 @@ pub struct Mesh {
     /// Flattened 3 component floating point vectors, storing positions of
     /// vertices in the mesh.
     pub positions: Vec<Float>,
+    /// The homogeneous `w` component of the position of each vertex in the
+    /// mesh. Positions without one have a `w` of `1`.
+    ///
+    /// *Empty* unless loaded with [`preserve_w`](LoadOptions::preserve_w) set
+    /// to `true` and the file specifies `w` for some positions.
+    pub position_w: Vec<Float>,
     /// Flattened 3 component floating point vectors, storing the color
     /// associated with the vertices in the mesh.
     ///
     /// Most meshes do not have vertex colors. If no vertex colors are specified
     /// this will be empty.
+    ///
+    /// Besides inline colors in the `v` statements, the colors of ZBrush
+    /// `#MRGB` blocks are loaded. Vertices without a color are white if others
+    /// have one.
     pub vertex_color: Vec<Float>,
+    /// The alpha of the color of each vertex in the mesh, from `v` statements
+    /// with seven values. Vertex colors without one have an alpha of `1`.
+    ///
+    /// *Empty* if the file doesn't specify alpha for any vertex colors.
+    pub vertex_color_alpha: Vec<Float>,
     /// Flattened 3 component floating point vectors, storing normals of
     /// vertices in the mesh.
     ///
     /// Not all meshes have normals. If no normals are specified this will
     /// be empty.
     pub normals: Vec<Float>,
+    /// Flattened 4 component floating point vectors, storing tangents of
+    /// vertices in the mesh.
+    ///
+    /// The `w` component is the handedness of the tangent space (`1` or `-1`),
+    /// i.e. the bitangent is `w * cross(normal, tangent)`.
+    ///
+    /// Only present when the `tangents` feature is enabled, and empty unless
+    /// generated via [`generate_tangents`](LoadOptions::generate_tangents) or
+    /// [`Mesh::generate_tangents()`].
+    #[cfg(feature = "tangents")]
+    pub tangents: Vec<Float>,
     /// Flattened 2 component floating point vectors, storing texture
     /// coordinates of vertices in the mesh.
     ///
     /// Not all meshes have texture coordinates. If no texture coordinates are
     /// specified this will be empty.
     pub texcoords: Vec<Float>,
+    /// The third component `w` of each texture coordinate in the mesh, e.g.
+    /// for 3D textures. Texture coordinates without one have a `w` of `0`.
+    ///
+    /// *Empty* unless loaded with [`preserve_w`](LoadOptions::preserve_w) set
+    /// to `true` and the file specifies `w` for some texture coordinates.
+    pub texcoord_w: Vec<Float>,
     /// Indices for vertices of each face. If loaded with
     /// [`triangulate`](LoadOptions::triangulate) set to `true` each face in the
     /// mesh is a triangle.
@@ -388,6 +460,42 @@ pub struct Mesh {
     /// through the `face_arities` until reaching the desired face, accumulating
     /// the number of vertices used so far.
     pub face_arities: Vec<u32>,
+    /// The smoothing group of each face as set by the `s` statements in the
+    /// `OBJ` file. A value of `0` means the face is not part of any smoothing
+    /// group (`s off`).
+    ///
+    /// There is one entry per face. If loaded with
+    /// [`triangulate`](LoadOptions::triangulate) set to `true` that is one
+    /// entry per triangle, i.e. the smoothing group of a polygon is repeated
+    /// for every triangle it was split into.
+    ///
+    /// *Empty* if the `OBJ` file has no `s` statement before any of the faces.
+    pub smoothing_groups: Vec<u32>,
+    /// Indices for points, as specified with the `p` statements and faces with
+    /// a single vertex in the `OBJ` file.
+    ///
+    /// Points share the vertex data of the faces, indexed like
+    /// [`line_indices`](Mesh::line_indices).
+    ///
+    /// *Empty* if loaded with [`ignore_points`](LoadOptions::ignore_points) set
+    /// to `true`.
+    pub point_indices: Vec<u32>,
+    /// Indices for vertices of each polyline, as specified with the `l`
+    /// statements in the `OBJ` file. [`line_arities`](Mesh::line_arities)
+    /// indicates how many indices are used by each polyline.
+    ///
+    /// Polylines share the vertex data of the faces. Like
+    /// [`indices`](Mesh::indices) these index all of the data in the mesh when
+    /// [`single_index`](LoadOptions::single_index) is set to `true`, and only
+    /// positions otherwise.
+    ///
+    /// *Empty* if loaded with [`ignore_lines`](LoadOptions::ignore_lines) set
+    /// to `true`.
+    pub line_indices: Vec<u32>,
+    /// The number of vertices of each polyline. Unlike
+    /// [`face_arities`](Mesh::face_arities) this is never empty if the mesh
+    /// has polylines.
+    pub line_arities: Vec<u32>,
     /// The indices for vertex colors. Only present when the
     /// [`merging`](LoadOptions::merge_identical_points) feature is enabled, and
     /// empty unless the corresponding load option is set to `true`.
@@ -399,9 +507,486 @@ pub struct Mesh {
     /// The indices for normals. Can be omitted by setting `single_index` to
     /// `true`.
     pub normal_indices: Vec<u32>,
+    /// The indices for texture coordinates of the vertices of each polyline,
+    /// see [`line_indices`](Mesh::line_indices).
+    ///
+    /// *Empty* if loaded with `single_index` set to `true` or if not all
+    /// polyline vertices have texture coordinates.
+    pub line_texcoord_indices: Vec<u32>,
+    /// The indices for tangents. Only present when the `tangents` feature is
+    /// enabled, and empty unless tangents were generated for a mesh without
+    /// a single index.
+    #[cfg(feature = "tangents")]
+    pub tangent_indices: Vec<u32>,
     /// Optional material id associated with this mesh. The material id indexes
     /// into the Vec of Materials loaded from the associated `MTL` file
+    ///
+    /// If loaded with
+    /// [`per_face_materials`](LoadOptions::per_face_materials) set to `true`,
+    /// this is only set if all faces of the mesh use the same material.
     pub material_id: Option<usize>,
+    /// The material id of each face. Like [`material_id`](Mesh::material_id)
+    /// each id indexes into the Vec of Materials loaded from the associated
+    /// `MTL` file.
+    ///
+    /// There is one entry per face, like for
+    /// [`smoothing_groups`](Mesh::smoothing_groups).
+    ///
+    /// *Empty* unless loaded with
+    /// [`per_face_materials`](LoadOptions::per_face_materials) set to `true`.
+    pub face_material_ids: Vec<Option<usize>>,
+    /// The vertex of the `OBJ` file each vertex was created from, i.e. the
+    /// indices of its position, texture coordinate and normal in the file.
+    ///
+    /// * With [`single_index`](LoadOptions::single_index) there is one entry
+    ///   per vertex.
+    ///
+    /// * Otherwise, positions, texture coordinates and normals are indexed
+    ///   separately, so there is one entry per element of
+    ///   [`indices`](Mesh::indices) instead. Vertices without a texture
+    ///   coordinate or normal index in a mesh that has them refer to the ones
+    ///   they were given in its place.
+    ///
+    /// *Empty* unless loaded with
+    /// [`source_vertices`](LoadOptions::source_vertices) set to `true`.
+    pub source_vertices: Vec<VertexIndices>,
+    /// The face of the `OBJ` file each face was created from, as the index of
+    /// the `f` statement among those of the model, starting at 0. Faces with
+    /// a single vertex are points and not counted.
+    ///
+    /// There is one entry per face, like for
+    /// [`smoothing_groups`](Mesh::smoothing_groups). All triangles of a
+    /// triangulated polygon share its index.
+    ///
+    /// *Empty* unless loaded with [`source_faces`](LoadOptions::source_faces)
+    /// set to `true`.
+    pub source_faces: Vec<u32>,
+}
+
+impl Mesh {
+    /// Generate normals for the mesh, replacing any normals it already has.
+    ///
+    /// This is what [`generate_normals`](LoadOptions::generate_normals) uses
+    /// for meshes loaded without normals. It is exposed for scenarios where
+    /// you want to (re-)generate normals after loading, e.g. after editing
+    /// positions.
+    ///
+    /// * With `single_index` set to `true` one normal is stored per vertex.
+    ///   Vertices that end up with more than one normal (e.g. on the edge
+    ///   between two smoothing groups) are duplicated and the `indices` changed
+    ///   accordingly.
+    ///
+    /// * Otherwise the generated normals get their own
+    ///   [`normal_indices`](Mesh::normal_indices) and the rest of the mesh is
+    ///   left untouched.
+    pub fn generate_normals(&mut self, mode: NormalGeneration, single_index: bool) {
+        let (weighting, crease_angle) = match mode {
+            NormalGeneration::Disabled => return,
+            NormalGeneration::Flat => (None, None),
+            NormalGeneration::Smooth {
+                weighting,
+                crease_angle,
+            } => (Some(weighting), crease_angle),
+        };
+
+        let faces = self.face_ranges();
+        let position = |v: u32| {
+            let v = v as usize * 3;
+            [
+                self.positions[v],
+                self.positions[v + 1],
+                self.positions[v + 2],
+            ]
+        };
+
+        // The (area weighted) face normal and unit length normal of each face.
+        let face_normals = faces
+            .iter()
+            .map(|face| {
+                let normal =
+                    polygon_normal(self.indices[face.clone()].iter().map(|&v| position(v)));
+                (normal, normalize(normal))
+            })
+            .collect::<Vec<_>>();
+
+        // The contribution of each corner to the normals of vertices sharing its
+        // position.
+        let mut corner_faces = vec![0; self.indices.len()];
+        let mut corner_normals = vec![[0.0; 3]; self.indices.len()];
+        for (f, face) in faces.iter().enumerate() {
+            for corner in face.clone() {
+                corner_faces[corner] = f;
+                corner_normals[corner] = match weighting {
+                    None => face_normals[f].1,
+                    Some(NormalWeighting::Area) => face_normals[f].0,
+                    Some(NormalWeighting::Angle) => {
+                        let prev = if corner == face.start {
+                            face.end - 1
+                        } else {
+                            corner - 1
+                        };
+                        let next = if corner + 1 == face.end {
+                            face.start
+                        } else {
+                            corner + 1
+                        };
+                        let p = position(self.indices[corner]);
+                        let angle = angle_between(
+                            sub(position(self.indices[prev]), p),
+                            sub(position(self.indices[next]), p),
+                        );
+                        scale(face_normals[f].1, angle)
+                    }
+                };
+            }
+        }
+
+        let mut normals = corner_normals.clone();
+        if weighting.is_some() {
+            // Corners are grouped by the value of their position, vertices of
+            // single index meshes may share it but differ in their other data.
+            let position_key = |v: u32| position(v).map(Float::to_bits);
+            let mut position_corners = HashMap::<_, Vec<usize>>::new();
+            for (corner, &v) in self.indices.iter().enumerate() {
+                position_corners
+                    .entry(position_key(v))
+                    .or_default()
+                    .push(corner);
+            }
+            let min_cos = crease_angle.map(Float::cos);
+            let smoothing_group = |f: usize| self.smoothing_groups.get(f).copied();
+
+            for (corner, normal) in normals.iter_mut().enumerate() {
+                let f = corner_faces[corner];
+                // Faces outside of any smoothing group keep their face normal.
+                if smoothing_group(f) == Some(0) {
+                    *normal = face_normals[f].1;
+                    continue;
+                }
+                let mut sum = [0.0; 3];
+                for &other in &position_corners[&position_key(self.indices[corner])] {
+                    let g = corner_faces[other];
+                    let is_smooth = g == f
+                        || (smoothing_group(g) == smoothing_group(f)
+                            && match min_cos {
+                                Some(min_cos) => {
+                                    dot(face_normals[f].1, face_normals[g].1) >= min_cos
+                                }
+                                None => true,
+                            });
+                    if is_smooth {
+                        sum = add(sum, corner_normals[other]);
+                    }
+                }
+                *normal = normalize(sum);
+            }
+        }
+
+        // Share normals between corners of a vertex that ended up with the same
+        // normal.
+        let mut normal_map = HashMap::new();
+        let mut normal_indices = Vec::with_capacity(self.indices.len());
+        let mut unique_normals = Vec::new();
+        for (&v, normal) in self.indices.iter().zip(&normals) {
+            let key = (v, normal.map(Float::to_bits));
+            let next = normal_map.len() as u32;
+            let index = *normal_map.entry(key).or_insert_with(|| {
+                unique_normals.push(*normal);
+                next
+            });
+            normal_indices.push(index);
+        }
+
+        if single_index {
+            // The first normal of a vertex is stored with the vertex itself,
+            // every other one requires a copy of the vertex.
+            let vertex_count = self.positions.len() / 3;
+            let mut vertex_normals = vec![None; vertex_count];
+            let mut copies = HashMap::new();
+            self.normals = vec![0.0; vertex_count * 3];
+            for (corner, &normal_index) in normal_indices.iter().enumerate() {
+                let v = self.indices[corner] as usize;
+                let vertex = match vertex_normals[v] {
+                    None => {
+                        vertex_normals[v] = Some(normal_index);
+                        v as u32
+                    }
+                    Some(first) if first == normal_index => v as u32,
+                    Some(_) => *copies
+                        .entry((v, normal_index))
+                        .or_insert_with(|| self.duplicate_vertex(v)),
+                };
+                let offset = vertex as usize * 3;
+                self.normals[offset..offset + 3]
+                    .copy_from_slice(&unique_normals[normal_index as usize]);
+                self.indices[corner] = vertex;
+            }
+            self.normal_indices = Vec::new();
+        } else {
+            self.normals = unique_normals.into_iter().flatten().collect();
+            self.normal_indices = normal_indices;
+        }
+    }
+
+    /// Generate tangents for the mesh from its positions, normals and texture
+    /// coordinates, replacing any tangents it already has.
+    ///
+    /// This is what [`generate_tangents`](LoadOptions::generate_tangents) uses.
+    /// Meshes lacking either normals or texture coordinates are left
+    /// untouched.
+    ///
+    /// The tangents follow the [MikkTSpace](http://www.mikktspace.com/)
+    /// conventions used by Blender, Substance and most other bakers: every
+    /// triangle's tangent is the direction of increasing `u` projected into the
+    /// tangent plane of the vertex normal, and averaged weighted by the angle
+    /// of the triangle at the vertex. Only triangles sharing position, normal,
+    /// texture coordinate and the handedness of their texture mapping are
+    /// averaged. Quads are split along their shorter diagonal in texture
+    /// space and larger polygons are split into triangle fans, like MikkTSpace
+    /// does.
+    ///
+    /// * With `single_index` set to `true` one tangent is stored per vertex.
+    ///   Vertices shared by triangles of different handedness (e.g. along the
+    ///   seam of mirrored texture coordinates) are duplicated and the `indices`
+    ///   changed accordingly.
+    ///
+    /// * Otherwise the generated tangents get their own
+    ///   [`tangent_indices`](Mesh::tangent_indices).
+    #[cfg(feature = "tangents")]
+    pub fn generate_tangents(&mut self, single_index: bool) {
+        if self.normals.is_empty() || self.texcoords.is_empty() {
+            return;
+        }
+
+        let position = |corner: usize| {
+            let v = self.indices[corner] as usize * 3;
+            [
+                self.positions[v],
+                self.positions[v + 1],
+                self.positions[v + 2],
+            ]
+        };
+        let normal_index = |corner: usize| {
+            *self
+                .normal_indices
+                .get(corner)
+                .unwrap_or(&self.indices[corner])
+        };
+        let texcoord_index = |corner: usize| {
+            *self
+                .texcoord_indices
+                .get(corner)
+                .unwrap_or(&self.indices[corner])
+        };
+        let normal = |corner: usize| {
+            let n = normal_index(corner) as usize * 3;
+            [self.normals[n], self.normals[n + 1], self.normals[n + 2]]
+        };
+        let texcoord = |corner: usize| {
+            let t = texcoord_index(corner) as usize * 2;
+            [self.texcoords[t], self.texcoords[t + 1]]
+        };
+
+        // Split all faces into triangles of corners.
+        let mut triangles = Vec::new();
+        for face in self.face_ranges() {
+            match face.len() {
+                0..=2 => {}
+                4 => {
+                    let [a, b, c, d] = [0, 1, 2, 3].map(|i| face.start + i);
+                    let distance = |a: usize, b: usize| {
+                        let (ta, tb) = (texcoord(a), texcoord(b));
+                        let uv_distance = (ta[0] - tb[0]).powi(2) + (ta[1] - tb[1]).powi(2);
+                        let offset = sub(position(a), position(b));
+                        (uv_distance, dot(offset, offset))
+                    };
+                    if distance(a, c) <= distance(b, d) {
+                        triangles.extend([[a, b, c], [a, c, d]]);
+                    } else {
+                        triangles.extend([[a, b, d], [b, c, d]]);
+                    }
+                }
+                _ => {
+                    let a = face.start;
+                    triangles.extend((a + 1..face.end - 1).map(|b| [a, b, b + 1]));
+                }
+            }
+        }
+        if triangles.is_empty() {
+            return;
+        }
+
+        // Corners are averaged if they share all of their attributes and the
+        // handedness of their triangle.
+        let group_key = |corner: usize, is_right_handed: bool| {
+            (
+                self.indices[corner],
+                normal_index(corner),
+                texcoord_index(corner),
+                is_right_handed,
+            )
+        };
+        let mut corner_groups = vec![None; self.indices.len()];
+        let mut group_map = HashMap::new();
+        let mut group_tangents = Vec::new();
+
+        for triangle in triangles {
+            let [p0, p1, p2] = triangle.map(position);
+            let [t0, t1, t2] = triangle.map(texcoord);
+            let (d1, d2) = (sub(p1, p0), sub(p2, p0));
+            let (t21, t31) = (
+                [t1[0] - t0[0], t1[1] - t0[1]],
+                [t2[0] - t0[0], t2[1] - t0[1]],
+            );
+
+            // Twice the signed area of the triangle in texture space.
+            let signed_area = t21[0] * t31[1] - t21[1] * t31[0];
+            let is_right_handed = signed_area > 0.0;
+            // Direction of increasing u, only defined for triangles with area in
+            // texture space.
+            let tangent = if signed_area.abs() > Float::EPSILON {
+                scale(
+                    normalize(sub(scale(d1, t31[1]), scale(d2, t21[1]))),
+                    signed_area.signum(),
+                )
+            } else {
+                [0.0; 3]
+            };
+
+            for i in 0..3 {
+                let corner = triangle[i];
+                let key = group_key(corner, is_right_handed);
+                let next_group = group_tangents.len();
+                let group = *group_map.entry(key).or_insert_with(|| {
+                    group_tangents.push([0.0; 3]);
+                    next_group
+                });
+                corner_groups[corner].get_or_insert(group);
+
+                let n = normal(corner);
+                let project = |v: [Float; 3]| normalize(sub(v, scale(n, dot(n, v))));
+                let p = position(corner);
+                let angle = angle_between(
+                    project(sub(position(triangle[(i + 1) % 3]), p)),
+                    project(sub(position(triangle[(i + 2) % 3]), p)),
+                );
+                group_tangents[group] = add(group_tangents[group], scale(project(tangent), angle));
+            }
+        }
+
+        // The tangent & handedness of each group.
+        let mut tangents = vec![[0.0; 4]; group_tangents.len()];
+        for (&(_, _, _, is_right_handed), &group) in &group_map {
+            let tangent = normalize(group_tangents[group]);
+            let w = if is_right_handed { 1.0 } else { -1.0 };
+            tangents[group] = [tangent[0], tangent[1], tangent[2], w];
+        }
+        // Corners without a tangent, e.g. on degenerate triangles, get an
+        // arbitrary one perpendicular to their normal.
+        for (corner, group) in corner_groups.iter().enumerate() {
+            if let Some(group) = *group {
+                if tangents[group][..3] == [0.0; 3] {
+                    let n = normal(corner);
+                    let axis = if n[0].abs() < 0.9 {
+                        [1.0, 0.0, 0.0]
+                    } else {
+                        [0.0, 1.0, 0.0]
+                    };
+                    let tangent = normalize(cross(n, axis));
+                    tangents[group][..3].copy_from_slice(&tangent);
+                }
+            }
+        }
+
+        if single_index {
+            // The first tangent of a vertex is stored with the vertex itself,
+            // every other one requires a copy of the vertex.
+            let vertex_count = self.positions.len() / 3;
+            let mut vertex_tangents = vec![None; vertex_count];
+            let mut copies = HashMap::new();
+            self.tangents = vec![0.0; vertex_count * 4];
+            for (corner, group) in corner_groups.into_iter().enumerate() {
+                let Some(group) = group else {
+                    continue;
+                };
+                let v = self.indices[corner] as usize;
+                let vertex = match vertex_tangents[v] {
+                    None => {
+                        vertex_tangents[v] = Some(group);
+                        v as u32
+                    }
+                    Some(first) if first == group => v as u32,
+                    Some(_) => *copies
+                        .entry((v, group))
+                        .or_insert_with(|| self.duplicate_vertex(v)),
+                };
+                let offset = vertex as usize * 4;
+                self.tangents[offset..offset + 4].copy_from_slice(&tangents[group]);
+                self.indices[corner] = vertex;
+            }
+            self.tangent_indices = Vec::new();
+        } else {
+            // Corners of points and lines don't get a tangent of their own.
+            self.tangent_indices = corner_groups
+                .into_iter()
+                .map(|group| group.unwrap_or_default() as u32)
+                .collect();
+            self.tangents = tangents.into_iter().flatten().collect();
+        }
+    }
+
+    /// The range of [`indices`](Mesh::indices) used by each face.
+    fn face_ranges(&self) -> Vec<std::ops::Range<usize>> {
+        if self.face_arities.is_empty() {
+            (0..self.indices.len() / 3)
+                .map(|f| f * 3..f * 3 + 3)
+                .collect()
+        } else {
+            self.face_arities
+                .iter()
+                .scan(0, |start, &arity| {
+                    let face = *start..*start + arity as usize;
+                    *start = face.end;
+                    Some(face)
+                })
+                .collect()
+        }
+    }
+
+    /// Append a copy of all the data of the vertex `v` to the mesh and return
+    /// the index of the copy.
+    ///
+    /// Only valid for meshes with a single index.
+    fn duplicate_vertex(&mut self, v: usize) -> u32 {
+        let copy = self.positions.len() / 3;
+        self.positions.extend_from_within(v * 3..v * 3 + 3);
+        if let Some(&w) = self.position_w.get(v) {
+            self.position_w.push(w);
+        }
+        if self.normals.len() >= (v + 1) * 3 {
+            self.normals.extend_from_within(v * 3..v * 3 + 3);
+        }
+        if self.texcoords.len() >= (v + 1) * 2 {
+            self.texcoords.extend_from_within(v * 2..v * 2 + 2);
+        }
+        if let Some(&w) = self.texcoord_w.get(v) {
+            self.texcoord_w.push(w);
+        }
+        if self.vertex_color.len() >= (v + 1) * 3 {
+            self.vertex_color.extend_from_within(v * 3..v * 3 + 3);
+        }
+        if let Some(&alpha) = self.vertex_color_alpha.get(v) {
+            self.vertex_color_alpha.push(alpha);
+        }
+        #[cfg(feature = "tangents")]
+        if self.tangents.len() >= (v + 1) * 4 {
+            self.tangents.extend_from_within(v * 4..v * 4 + 4);
+        }
+        if let Some(&source) = self.source_vertices.get(v) {
+            self.source_vertices.push(source);
+        }
+        copy as u32
+    }
 }
 
 /// Options for processing the mesh during loading.
@@ -437,8 +1022,8 @@ pub struct LoadOptions {
     ///
     /// * This flag is *mutually exclusive* with
     ///   [`single_index`](LoadOptions::single_index) and will lead to a
-    ///   [`InvalidLoadOptionConfig`](LoadError::InvalidLoadOptionConfig) error
-    ///   if both are set to `true`.
+    ///   [`InvalidLoadOptionConfig`](LoadErrorKind::InvalidLoadOptionConfig)
+    ///   error if both are set to `true`.
     ///
     /// * If adjacent faces share vertices that have separate `indices` but the
     ///   same position in 3D they will be merged into a single vertex and the
@@ -453,8 +1038,8 @@ pub struct LoadOptions {
     ///
     /// * This flag is *mutually exclusive* with
     ///   [`single_index`](LoadOptions::single_index) and will lead to an
-    ///   [`InvalidLoadOptionConfig`](LoadError::InvalidLoadOptionConfig) error
-    ///   if both are set to `true`.
+    ///   [`InvalidLoadOptionConfig`](LoadErrorKind::InvalidLoadOptionConfig)
+    ///   error if both are set to `true`.
     ///
     /// * The resulting [`Mesh`]'s `normal_indices` and/or `texcoord_indices`
     ///   will be empty.
@@ -487,8 +1072,8 @@ pub struct LoadOptions {
     /// * This flag is *mutually exclusive* with both
     ///   [`merge_identical_points`](LoadOptions::merge_identical_points) and
     ///   [`reorder_data`](LoadOptions::reorder_data) resp. and will lead to a
-    ///   [`InvalidLoadOptionConfig`](LoadError::InvalidLoadOptionConfig) error
-    ///   if both it and either of the two other are set to `true`.
+    ///   [`InvalidLoadOptionConfig`](LoadErrorKind::InvalidLoadOptionConfig)
+    ///   error if both it and either of the two other are set to `true`.
     ///
     /// * Vertices may get duplicated to match the granularity
     ///   (*per-vertex-per-face*) of normals and/or texture coordinates.
@@ -501,19 +1086,27 @@ pub struct LoadOptions {
     pub single_index: bool,
     /// Triangulate all faces.
     ///
-    /// * Points (one point) and lines (two points) are blown up to zero area
-    ///   triangles via point duplication. Except if `ignore_points` or
-    ///   `ignore_lines` is/are set to `true`, resp.
+    /// * Lines (faces with two points) are blown up to zero area triangles via
+    ///   point duplication.
+    ///
+    /// * Points and polylines are never triangulated, see
+    ///   [`point_indices`](Mesh::point_indices) and
+    ///   [`line_indices`](Mesh::line_indices).
     ///
     /// * The resulting `Mesh`'s [`face_arities`](Mesh::face_arities) will be
     ///   empty as all faces are guaranteed to have arity `3`.
     ///
-    /// * Only polygons that are trivially convertible to triangle fans are
-    ///   supported. Arbitrary polygons may not behave as expected. The best
-    ///   solution would be to convert your mesh to solely consist of triangles
-    ///   in your modeling software.
+    /// * How polygons are split into triangles is governed by
+    ///   [`triangulation`](LoadOptions::triangulation).
     pub triangulate: bool,
-    /// Ignore faces containing only a single vertex (points).
+    /// The method used to split polygons into triangles if
+    /// [`triangulate`](LoadOptions::triangulate) is set to `true`.
+    ///
+    /// Defaults to [`Triangulation::Fan`] which is fast but only correct for
+    /// convex polygons.
+    pub triangulation: Triangulation,
+    /// Ignore points, as specified with `p` statements and faces containing
+    /// only a single vertex.
     ///
     /// This is usually what you want if you do *not* intend to make special use
     /// of the point data (e.g. as particles etc.).
@@ -521,14 +1114,107 @@ pub struct LoadOptions {
     /// Polygon meshes that contain faces with one vertex only usually do so
     /// because of bad topology.
     pub ignore_points: bool,
-    /// Ignore faces containing only two vertices (lines).
+    /// Ignore polylines, as specified with `l` statements.
     ///
     /// This is usually what you want if you do *not* intend to make special use
     /// of the line data (e.g. as wires/ropes etc.).
     ///
-    /// Polygon meshes that contains faces with two vertices only usually do so
-    /// because of bad topology.
+    /// Faces with two vertices are not affected by this.
     pub ignore_lines: bool,
+    /// Keep the faces of an object together when its material changes.
+    ///
+    /// By default every `usemtl` statement inside an object starts a new
+    /// [`Model`] with the same name, as each [`Mesh`] only has a single
+    /// [`material_id`](Mesh::material_id).
+    ///
+    /// * With this flag set, a new `Model` is only started by `o` and `g`
+    ///   statements and the material of each face is stored in the
+    ///   [`face_material_ids`](Mesh::face_material_ids) of its `Mesh` instead.
+    ///
+    /// * Vertices shared by faces with different materials are not duplicated.
+    pub per_face_materials: bool,
+    /// Generate normals for meshes that have none.
+    ///
+    /// * Meshes that have normals in the `OBJ` file are left as they are.
+    ///
+    /// * Smoothing groups are honored, see [`NormalGeneration::Smooth`].
+    ///
+    /// * Normals are generated before merging and/or reordering.
+    ///
+    /// See [`Mesh::generate_normals()`] for how the generated normals are
+    /// stored.
+    pub generate_normals: NormalGeneration,
+    /// Generate MikkTSpace tangents for meshes that have normals and texture
+    /// coordinates.
+    ///
+    /// * Tangents are generated after normals, so meshes without normals get
+    ///   tangents too if [`generate_normals`](LoadOptions::generate_normals) is
+    ///   set.
+    ///
+    /// See [`Mesh::generate_tangents()`] for how the generated tangents are
+    /// stored.
+    #[cfg(feature = "tangents")]
+    pub generate_tangents: bool,
+    /// Skip lines that fail to parse instead of failing to load the file.
+    ///
+    /// * Faces with out of bounds indices are skipped as well.
+    ///
+    /// * Skipped `v`, `vt`, `vn` and `vp` statements are replaced by zeros, so
+    ///   the indices of the following ones stay the same.
+    ///
+    /// * The errors of all skipped lines are returned with the loaded models as
+    ///   [`LoadWarning::SkippedLine`]s, see [`LoadResult`].
+    ///
+    /// * Errors reading the file, other than lines which are not valid UTF-8,
+    ///   still fail the load.
+    pub skip_invalid_lines: bool,
+    /// Record the vertex of the `OBJ` file each vertex of a mesh was created
+    /// from in [`Mesh::source_vertices`].
+    pub source_vertices: bool,
+    /// Record the face of the `OBJ` file each face of a mesh was created from
+    /// in [`Mesh::source_faces`].
+    pub source_faces: bool,
+    /// Tessellate free-form surfaces into the mesh of their model, splitting
+    /// them into the given number of segments in u and v direction.
+    ///
+    /// * The triangles of each surface are added to the mesh after its faces,
+    ///   see [`Surface::tessellate()`]. They count as faces of their own for
+    ///   [`source_faces`](LoadOptions::source_faces).
+    ///
+    /// * Surfaces that can't be tessellated are reported as
+    ///   [`LoadWarning::UntessellatedSurface`]s.
+    ///
+    /// * Surfaces are kept in the [`free_form`](Model::free_form) geometry of
+    ///   the model regardless. Raw shapes are never tessellated.
+    pub tessellate_surfaces: Option<u32>,
+    /// Load the vertices that are not used by any face, point, polyline or
+    /// free-form geometry as a point cloud, e.g. for scans that only consist
+    /// of `v` and `vn` statements.
+    ///
+    /// * The point cloud is an additional [`Model`] after all others, with a
+    ///   point for each of these vertices, see
+    ///   [`point_indices`](Mesh::point_indices). If the last model has no
+    ///   geometry, the point cloud takes its place, name and material.
+    ///   Otherwise it is named `unnamed_object` and is in no object, group or
+    ///   material.
+    ///
+    /// * Vertex colors are loaded along with the positions. Normals are only
+    ///   loaded if the file has as many normals as positions, they are stored
+    ///   per vertex.
+    ///
+    /// * Raw shapes get the point cloud as a [`RawShape`] with
+    ///   [`point_indices`](RawShape::point_indices).
+    pub point_cloud: bool,
+    /// Keep the `w` component of positions and texture coordinates, see
+    /// [`Mesh::position_w`] and [`Mesh::texcoord_w`].
+    ///
+    /// A `v` statement with four values is a position with `w`, one with six
+    /// or seven values is a position followed by a vertex color. Texture
+    /// coordinates have `w` if their `vt` statement has three values.
+    ///
+    /// The `w` of positions is used as the weight of the control points of
+    /// free-form geometry regardless of this option.
+    pub preserve_w: bool,
 }
 
 impl LoadOptions {
@@ -557,183 +1243,1475 @@ impl LoadOptions {
     }
 }
 
+/// How polygons are split into triangles when
+/// [`triangulate`](LoadOptions::triangulate) is set.
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Triangulation {
+    /// Split polygons into triangle fans around their first vertex.
+    ///
+    /// Only polygons that are trivially convertible to triangle fans (i.e.
+    /// convex ones) are supported. Concave polygons get overlapping triangles.
+    #[default]
+    Fan,
+    /// Split quads and polygons by ear clipping on the plane that best fits
+    /// them.
+    ///
+    /// Slower than a fan but also handles concave and non-planar polygons.
+    /// Self-intersecting polygons have no correct triangulation and still
+    /// produce overlapping triangles.
+    EarClipping,
+}
+
+/// How normals are generated for meshes without normals.
+///
+/// See [`LoadOptions::generate_normals`] and [`Mesh::generate_normals()`].
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum NormalGeneration {
+    /// Do not generate normals.
+    #[default]
+    Disabled,
+    /// Every face gets its own normal, faceting the mesh.
+    Flat,
+    /// Vertices get the weighted average of the normals of the faces sharing
+    /// their position.
+    ///
+    /// * Only faces in the same smoothing group are averaged. Faces outside of
+    ///   any smoothing group (`s off`) get flat normals. If the mesh has no
+    ///   smoothing groups at all, all faces are averaged.
+    ///
+    /// * If a `crease_angle` (in radians) is given, faces whose normals differ
+    ///   by more than it are not averaged, keeping hard edges sharp.
+    Smooth {
+        weighting: NormalWeighting,
+        crease_angle: Option<Float>,
+    },
+}
+
+/// How face normals are weighted when averaging them into smooth vertex
+/// normals.
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum NormalWeighting {
+    /// Weight by the area of the faces, larger faces have more influence.
+    #[default]
+    Area,
+    /// Weight by the angle of the faces at the vertex. Gives results
+    /// independent of how the faces around a vertex are tessellated.
+    Angle,
+}
+
 /// A named model within the file.
 ///
 /// Associates some mesh with a name that was specified with an `o` or `g`
 /// keyword in the `OBJ` file.
+///
+/// A new model is started by every `o` and `g` keyword, so all faces of a
+/// model belong to the same object and groups.
 #[derive(Clone, Debug)]
 pub struct Model {
     /// [`Mesh`] used by the model containing its geometry.
     pub mesh: Mesh,
     /// Name assigned to this `Mesh`.
+    ///
+    /// This is the text after the last `o` or `g` keyword, whichever came last.
+    /// Use [`object`](Model::object) and [`groups`](Model::groups) to tell them
+    /// apart.
     pub name: String,
+    /// Name of the object the model is part of, as specified with an `o`
+    /// keyword.
+    pub object: Option<String>,
+    /// Names of the groups the model is part of, as specified with a `g`
+    /// keyword.
+    ///
+    /// A `g` keyword may name several groups, e.g. `g body left_arm`. An `o`
+    /// keyword resets the groups.
+    pub groups: Vec<String>,
+    /// Free-form curves and surfaces of the model, as specified with the
+    /// `curv`, `curv2` and `surf` keywords.
+    ///
+    /// These are not part of the [`mesh`](Model::mesh).
+    pub free_form: FreeForm,
 }
 
 impl Model {
     /// Create a new model, associating a name with a [`Mesh`].
+    ///
+    /// The model is not part of any object or group.
     pub fn new(mesh: Mesh, name: String) -> Model {
-        Model { mesh, name }
+        Model {
+            mesh,
+            name,
+            object: None,
+            groups: Vec::new(),
+            free_form: FreeForm::default(),
+        }
     }
 }
 
-/// A material that may be referenced by one or more [`Mesh`]es.
+/// The models of a file arranged the way they were authored: objects
+/// containing groups containing a submesh per material.
 ///
-/// Standard `MTL` attributes are supported. Any unrecognized parameters will be
-/// stored as key-value pairs in the `unknown_param`
-/// [`HashMap`](std::collections::HashMap), which maps the unknown parameter to
-/// the value set for it.
+/// Models are split on every `o`, `g` and `usemtl` keyword, so several models
+/// loaded from a file can share the same name. A scene brings models that are
+/// part of the same object and groups back together.
 ///
-/// No path is pre-pended to the texture file names specified in the `MTL` file.
+/// All ids are assigned in the order the models appear in, so loading the
+/// same file again results in the same ids.
+///
+/// # Example
+///
+/// ```
+/// let (models, _, _) = tobj::load_obj("obj/cornell_box.obj", &tobj::GPU_LOAD_OPTIONS).unwrap();
+/// let scene = tobj::Scene::from_models(models);
+///
+/// for object in &scene.objects {
+///     println!("object {:?}", object.name);
+///     for group in &object.groups {
+///         println!("  group {:?}", group.names);
+///         for submesh in &group.submeshes {
+///             println!("    material {:?}", submesh.mesh.material_id);
+///         }
+///     }
+/// }
+/// ```
 #[derive(Clone, Debug, Default)]
-pub struct Material {
-    /// Material name as specified in the `MTL` file.
-    pub name: String,
-    /// Ambient color of the material.
-    pub ambient: Option<[Float; 3]>,
-    /// Diffuse color of the material.
-    pub diffuse: Option<[Float; 3]>,
-    /// Specular color of the material.
-    pub specular: Option<[Float; 3]>,
-    /// Emissive color of the material.
-    pub emissive: Option<[Float; 3]>,
-    /// Material shininess attribute. Also called `glossiness`.
-    pub shininess: Option<Float>,
-    /// Dissolve attribute is the alpha term for the material. Referred to as
-    /// dissolve since that's what the `MTL` file format docs refer to it as.
-    pub dissolve: Option<Float>,
-    /// Optical density also known as index of refraction. Called
-    /// `optical_density` in the `MTL` specc. Takes on a value between 0.001
-    /// and 10.0. 1.0 means light does not bend as it passes through
-    /// the object.
-    pub optical_density: Option<Float>,
-    /// Name of the ambient texture file for the material.
-    pub ambient_texture: Option<String>,
-    /// Name of the diffuse texture file for the material.
-    pub diffuse_texture: Option<String>,
-    /// Name of the specular texture file for the material.
-    pub specular_texture: Option<String>,
-    /// Name of the normal map texture file for the material.
-    pub normal_texture: Option<String>,
-    /// Name of the shininess map texture file for the material.
-    pub shininess_texture: Option<String>,
-    /// Name of the alpha/opacity map texture file for the material.
-    ///
-    /// Referred to as `dissolve` to match the `MTL` file format specification.
-    pub dissolve_texture: Option<String>,
-    /// The illumnination model to use for this material. The different
-    /// illumination models are specified in the [`MTL` spec](http://paulbourke.net/dataformats/mtl/).
-    pub illumination_model: Option<u8>,
-    /// Key value pairs of any unrecognized parameters encountered while parsing
-    /// the material.
-    pub unknown_param: HashMap<String, String>,
+pub struct Scene {
+    /// The objects of the scene.
+    pub objects: Vec<SceneObject>,
 }
 
-/// Possible errors that may occur while loading `OBJ` and `MTL` files.
-#[derive(Debug, Clone, Copy, PartialEq)]
-pub enum LoadError {
-    OpenFileFailed,
-    ReadError,
-    UnrecognizedCharacter,
-    PositionParseError,
-    NormalParseError,
-    TexcoordParseError,
-    FaceParseError,
-    MaterialParseError,
-    InvalidObjectName,
-    InvalidPolygon,
-    FaceVertexOutOfBounds,
-    FaceTexCoordOutOfBounds,
-    FaceNormalOutOfBounds,
-    FaceColorOutOfBounds,
-    InvalidLoadOptionConfig,
-    GenericFailure,
+/// An object of a [`Scene`], as specified with an `o` keyword.
+#[derive(Clone, Debug)]
+pub struct SceneObject {
+    /// Id of the object, its index in [`Scene::objects`].
+    pub id: usize,
+    /// Name of the object, `None` for the faces before the first `o` keyword.
+    pub name: Option<String>,
+    /// The groups of the object.
+    pub groups: Vec<SceneGroup>,
 }
 
-impl fmt::Display for LoadError {
-    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
-        let msg = match *self {
-            LoadError::OpenFileFailed => "open file failed",
-            LoadError::ReadError => "read error",
-            LoadError::UnrecognizedCharacter => "unrecognized character",
-            LoadError::PositionParseError => "position parse error",
-            LoadError::NormalParseError => "normal parse error",
-            LoadError::TexcoordParseError => "texcoord parse error",
-            LoadError::FaceParseError => "face parse error",
-            LoadError::MaterialParseError => "material parse error",
-            LoadError::InvalidObjectName => "invalid object name",
-            LoadError::InvalidPolygon => "invalid polygon",
-            LoadError::FaceVertexOutOfBounds => "face vertex index out of bounds",
-            LoadError::FaceTexCoordOutOfBounds => "face texcoord index out of bounds",
-            LoadError::FaceNormalOutOfBounds => "face normal index out of bounds",
-            LoadError::FaceColorOutOfBounds => "face vertex color index out of bounds",
-            LoadError::InvalidLoadOptionConfig => "mutually exclusive load options",
-            LoadError::GenericFailure => "generic failure",
-        };
-
-        f.write_str(msg)
-    }
+/// A group of a [`SceneObject`], as specified with a `g` keyword.
+#[derive(Clone, Debug)]
+pub struct SceneGroup {
+    /// Id of the group, unique within the scene.
+    pub id: usize,
+    /// Names of the group, empty for the faces of an object before its first
+    /// `g` keyword.
+    pub names: Vec<String>,
+    /// The submeshes of the group, one for each time the material changes.
+    pub submeshes: Vec<Submesh>,
 }
 
-impl Error for LoadError {}
-
-/// A [`Result`] containing all the models loaded from the file and any
-/// materials from referenced material libraries. Or an error that occurred
-/// while loading.
-pub type LoadResult = Result<(Vec<Model>, Result<Vec<Material>, LoadError>), LoadError>;
-
-/// A [`Result`] containing all the materials loaded from the file and a map of
-/// `MTL` name to index. Or an error that occurred while loading.
-pub type MTLLoadResult = Result<(Vec<Material>, HashMap<String, usize>), LoadError>;
-
-/// Struct storing indices corresponding to the vertex.
-///
-/// Some vertices may not have texture coordinates or normals, 0 is used to
-/// indicate this as OBJ indices begin at 1
-#[derive(Hash, Eq, PartialEq, PartialOrd, Ord, Debug, Copy, Clone)]
-struct VertexIndices {
-    pub v: usize,
-    pub vt: usize,
-    pub vn: usize,
+/// A part of a [`SceneGroup`] using a single material.
+#[derive(Clone, Debug)]
+pub struct Submesh {
+    /// Id of the submesh, the index of the [`Model`] it was created from.
+    pub id: usize,
+    /// Geometry of the submesh.
+    ///
+    /// Its [`material_id`](Mesh::material_id) is the material of the submesh.
+    /// Models loaded with
+    /// [`per_face_materials`](LoadOptions::per_face_materials) are not split by
+    /// material, so their submeshes may have
+    /// [`face_material_ids`](Mesh::face_material_ids) instead.
+    pub mesh: Mesh,
 }
 
-static MISSING_INDEX: usize = usize::MAX;
-
-impl VertexIndices {
-    /// Parse the vertex indices from the face string.
-    ///
-    /// Valid face strings are those that are valid for a Wavefront `OBJ` file.
-    ///
-    /// Also handles relative face indices (negative values) which is why
-    /// passing the number of positions, texcoords and normals is required.
+impl Scene {
+    /// Arrange the models, as returned by [`load_obj()`] and the like, into a
+    /// scene.
     ///
-    /// Returns `None` if the face string is invalid.
-    fn parse(
-        face_str: &str,
+    /// Models that are part of the same object and groups are gathered as
+    /// submeshes of one group, also when a file returns to an object or group
+    /// after others.
+    pub fn from_models(models: Vec<Model>) -> Scene {
+        let mut objects: Vec<SceneObject> = Vec::new();
+        let mut group_count = 0;
+        for (id, model) in models.into_iter().enumerate() {
+            let object = match objects.iter().position(|o| o.name == model.object) {
+                Some(index) => &mut objects[index],
+                None => {
+                    objects.push(SceneObject {
+                        id: objects.len(),
+                        name: model.object,
+                        groups: Vec::new(),
+                    });
+                    objects.last_mut().unwrap()
+                }
+            };
+            let group = match object.groups.iter().position(|g| g.names == model.groups) {
+                Some(index) => &mut object.groups[index],
+                None => {
+                    object.groups.push(SceneGroup {
+                        id: group_count,
+                        names: model.groups,
+                        submeshes: Vec::new(),
+                    });
+                    group_count += 1;
+                    object.groups.last_mut().unwrap()
+                }
+            };
+            group.submeshes.push(Submesh {
+                id,
+                mesh: model.mesh,
+            });
+        }
+        Scene { objects }
+    }
+}
+
+/// The geometry of a file as it was specified, returned by [`load_obj_raw()`].
+///
+/// Unlike the [`Mesh`]es of [`Model`]s, which only contain the vertex data
+/// their faces use, all vertex data of the file is stored once and shared by
+/// all [`RawShape`]s. Values are stored packed in flat `Vec`s like in a
+/// [`Mesh`] and their indices are those of the file.
+#[derive(Clone, Debug, Default)]
+pub struct RawObj {
+    /// Flattened 3 component floating point vectors, storing positions of
+    /// vertices in the file.
+    pub positions: Vec<Float>,
+    /// The `w` component of each position, see [`Mesh::position_w`].
+    pub position_w: Vec<Float>,
+    /// Flattened 3 component floating point vectors, storing the color
+    /// associated with the vertices in the file.
+    ///
+    /// *Empty* if the file doesn't specify vertex colors.
+    pub vertex_color: Vec<Float>,
+    /// The alpha of each vertex color, see [`Mesh::vertex_color_alpha`].
+    pub vertex_color_alpha: Vec<Float>,
+    /// Flattened 2 component floating point vectors, storing the texture
+    /// coordinates in the file.
+    pub texcoords: Vec<Float>,
+    /// The `w` component of each texture coordinate, see
+    /// [`Mesh::texcoord_w`].
+    pub texcoord_w: Vec<Float>,
+    /// Flattened 3 component floating point vectors, storing the normals in
+    /// the file.
+    pub normals: Vec<Float>,
+    /// The shapes of the file, one for each model [`load_obj()`] would return.
+    pub shapes: Vec<RawShape>,
+}
+
+/// The faces of a part of a file, referencing the vertex data of a
+/// [`RawObj`].
+///
+/// Faces are kept as they are specified in the file, e.g. they are not
+/// triangulated.
+#[derive(Clone, Debug, Default)]
+pub struct RawShape {
+    /// Name of the shape, see [`Model::name`].
+    pub name: String,
+    /// Name of the object the shape is part of, see [`Model::object`].
+    pub object: Option<String>,
+    /// Names of the groups the shape is part of, see [`Model::groups`].
+    pub groups: Vec<String>,
+    /// The vertices of all faces, indexing into the vertex data of the
+    /// [`RawObj`].
+    pub indices: Vec<VertexIndices>,
+    /// The number of vertices (arity) of each face, unlike
+    /// [`Mesh::face_arities`] this is never empty.
+    pub face_arities: Vec<u32>,
+    /// The smoothing group of each face, see [`Mesh::smoothing_groups`].
+    pub smoothing_groups: Vec<u32>,
+    /// Material of the shape, see [`Mesh::material_id`].
+    pub material_id: Option<usize>,
+    /// The material of each face, see [`Mesh::face_material_ids`].
+    pub face_material_ids: Vec<Option<usize>>,
+    /// The vertices of all points, indexing into the vertex data of the
+    /// [`RawObj`].
+    pub point_indices: Vec<VertexIndices>,
+    /// The vertices of all polylines, indexing into the vertex data of the
+    /// [`RawObj`].
+    pub line_indices: Vec<VertexIndices>,
+    /// The number of vertices of each polyline, see [`Mesh::line_arities`].
+    pub line_arities: Vec<u32>,
+    /// Free-form curves and surfaces of the shape, see [`Model::free_form`].
+    pub free_form: FreeForm,
+}
+
+/// The type of basis of a free-form curve or surface, as specified with a
+/// `cstype` keyword.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BasisType {
+    /// `bmatrix`, the basis is given by [`Basis::matrix`].
+    BasisMatrix,
+    /// `bezier`
+    Bezier,
+    /// `bspline`, a non-uniform B-spline, or NURBS if rational.
+    BSpline,
+    /// `cardinal`
+    Cardinal,
+    /// `taylor`
+    Taylor,
+}
+
+/// The basis of a free-form curve or surface, as specified with the `cstype`,
+/// `deg`, `bmat` and `step` keywords before it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Basis {
+    /// Type of the basis.
+    pub basis_type: BasisType,
+    /// Whether the basis is rational, i.e. the weights of the control points
+    /// apply.
+    pub rational: bool,
+    /// Degree in u and v direction. Curves only have a u direction.
+    pub degree: [u32; 2],
+    /// Basis matrices in u and v direction, row by row. Each row holds the
+    /// coefficients of the increasing powers of the parameter for a control
+    /// point. *Empty* unless specified for a [`BasisType::BasisMatrix`].
+    pub matrix: [Vec<Float>; 2],
+    /// Step sizes in u and v direction, `0` unless specified.
+    pub step: [u32; 2],
+}
+
+/// A free-form curve in space, as specified with a `curv` keyword.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Curve {
+    /// Basis of the curve.
+    pub basis: Basis,
+    /// Start and end of the curve in its parameter space.
+    pub parameter_range: [Float; 2],
+    /// Control points as x, y, z and weight. The weight is `1` for vertices
+    /// without one.
+    pub control_points: Vec<[Float; 4]>,
+    /// Knot vector, as specified with `parm u`.
+    pub knots: Vec<Float>,
+    /// Special points, as specified with `sp`, as parameter space vertices.
+    pub special_points: Vec<[Float; 3]>,
+}
+
+/// A free-form curve in the parameter space of a surface, as specified with a
+/// `curv2` keyword.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Curve2D {
+    /// Basis of the curve.
+    pub basis: Basis,
+    /// Control points as u, v and weight, from the parameter space vertices
+    /// (`vp`) of the file. Missing v coordinates are `0` and missing weights
+    /// `1`.
+    pub control_points: Vec<[Float; 3]>,
+    /// Knot vector, as specified with `parm u`.
+    pub knots: Vec<Float>,
+    /// Special points, as specified with `sp`, as parameter space vertices.
+    pub special_points: Vec<[Float; 3]>,
+}
+
+/// A part of a [`Curve2D`], as referenced by the `trim`, `hole` and `scrv`
+/// keywords of a surface.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CurveSegment {
+    /// Start and end of the segment in the parameter space of the curve.
+    pub parameter_range: [Float; 2],
+    /// The curve the segment is part of.
+    pub curve: Curve2D,
+}
+
+/// A free-form surface, as specified with a `surf` keyword.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Surface {
+    /// Basis of the surface.
+    pub basis: Basis,
+    /// Start and end of the surface in its parameter space, in u and v
+    /// direction.
+    pub parameter_range: [[Float; 2]; 2],
+    /// Control points as x, y, z and weight, row by row in u direction. The
+    /// weight is `1` for vertices without one.
+    pub control_points: Vec<[Float; 4]>,
+    /// Texture coordinates of the control points. *Empty* unless all control
+    /// points specify one.
+    pub texcoords: Vec<[Float; 2]>,
+    /// Normals of the control points. *Empty* unless all control points
+    /// specify one.
+    pub normals: Vec<[Float; 3]>,
+    /// Knot vectors in u and v direction, as specified with `parm u` and
+    /// `parm v`.
+    pub knots: [Vec<Float>; 2],
+    /// Outer trimming loops, as specified with `trim`. The surface is limited
+    /// to the area within them.
+    pub trims: Vec<Vec<CurveSegment>>,
+    /// Inner trimming loops, as specified with `hole`. The area within them is
+    /// cut out of the surface.
+    pub holes: Vec<Vec<CurveSegment>>,
+    /// Special curves, as specified with `scrv`, that must be part of any
+    /// tessellation of the surface.
+    pub special_curves: Vec<Vec<CurveSegment>>,
+    /// Special points, as specified with `sp`, as parameter space vertices.
+    pub special_points: Vec<[Float; 3]>,
+}
+
+/// The free-form geometry of a [`Model`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FreeForm {
+    /// Curves in space.
+    pub curves: Vec<Curve>,
+    /// Curves in the parameter space of surfaces.
+    pub curves_2d: Vec<Curve2D>,
+    /// Surfaces.
+    pub surfaces: Vec<Surface>,
+}
+
+impl FreeForm {
+    /// Whether there is no free-form geometry.
+    pub fn is_empty(&self) -> bool {
+        self.curves.is_empty() && self.curves_2d.is_empty() && self.surfaces.is_empty()
+    }
+}
+
+/// Whether the values of a parameter vector are finite and non-decreasing.
+fn valid_knots(knots: &[Float]) -> bool {
+    knots.iter().all(|knot| knot.is_finite()) && knots.windows(2).all(|pair| pair[0] <= pair[1])
+}
+
+impl Basis {
+    /// Number of control points in `direction` for the parameter vector
+    /// `knots`, `None` if the basis is not supported or the knots are invalid.
+    fn control_point_count(&self, direction: usize, knots: &[Float]) -> Option<usize> {
+        if !valid_knots(knots) {
+            return None;
+        }
+        let degree = self.degree[direction] as usize;
+        let segments = knots
+            .len()
+            .checked_sub(1)
+            .filter(|&segments| segments > 0)?;
+        match self.basis_type {
+            BasisType::BSpline => knots.len().checked_sub(degree + 1).filter(|&n| n > 0),
+            BasisType::Bezier if degree > 0 => segments.checked_mul(degree)?.checked_add(1),
+            BasisType::Bezier => None,
+            _ => (segments - 1)
+                .checked_mul(self.step_size(direction)?)?
+                .checked_add(degree + 1),
+        }
+    }
+
+    /// Number of control points between segments of a basis matrix.
+    fn step_size(&self, direction: usize) -> Option<usize> {
+        let degree = self.degree[direction] as usize;
+        match (self.basis_type, self.step[direction]) {
+            (BasisType::Cardinal, 0) => Some(1),
+            (BasisType::Taylor, 0) => Some(degree + 1),
+            (_, 0) => None,
+            (_, step) => Some(step as usize),
+        }
+    }
+
+    /// Weights of the control points in `direction` at parameter `t` of the
+    /// parameter vector `knots`, along with the index of the first control
+    /// point they apply to.
+    fn weights(&self, direction: usize, knots: &[Float], t: Float) -> Option<(usize, Vec<Float>)> {
+        let degree = self.degree[direction] as usize;
+        let count = self.control_point_count(direction, knots)?;
+        if self.basis_type == BasisType::BSpline {
+            // Span of the knot vector `t` lies in, clamped to the valid range
+            let t = t.clamp(knots[degree], knots[count]);
+            let span = (degree..count)
+                .rev()
+                .find(|&i| knots[i] <= t && knots[i] < knots[i + 1])?;
+            // Cox-de Boor recursion
+            let mut weights = vec![0.0; degree + 1];
+            let mut left = vec![0.0; degree + 1];
+            let mut right = vec![0.0; degree + 1];
+            weights[0] = 1.0;
+            for j in 1..=degree {
+                left[j] = t - knots[span + 1 - j];
+                right[j] = knots[span + j] - t;
+                let mut saved = 0.0;
+                for r in 0..j {
+                    let tmp = weights[r] / (right[r + 1] + left[j - r]);
+                    weights[r] = saved + right[r + 1] * tmp;
+                    saved = left[j - r] * tmp;
+                }
+                weights[j] = saved;
+            }
+            return Some((span - degree, weights));
+        }
+
+        // All other bases consist of polynomial segments between the values of
+        // the parameter vector
+        let segments = knots.len() - 1;
+        let t = t.clamp(knots[0], knots[segments]);
+        let segment = (0..segments)
+            .find(|&i| t <= knots[i + 1])
+            .unwrap_or(segments - 1);
+        let length = knots[segment + 1] - knots[segment];
+        let s = if length > 0.0 {
+            (t - knots[segment]) / length
+        } else {
+            0.0
+        };
+        if self.basis_type == BasisType::Bezier {
+            // Bernstein polynomials
+            let mut binomial = 1.0;
+            let weights = (0..=degree)
+                .map(|i| {
+                    let weight = binomial * s.powi(i as i32) * (1.0 - s).powi((degree - i) as i32);
+                    binomial = binomial * (degree - i) as Float / (i + 1) as Float;
+                    weight
+                })
+                .collect();
+            return Some((segment * degree, weights));
+        }
+
+        // Coefficients of the powers of `s` for each control point
+        let matrix = match self.basis_type {
+            BasisType::Cardinal if degree == 3 => vec![
+                0.0, -0.5, 1.0, -0.5, 1.0, 0.0, -2.5, 1.5, 0.0, 0.5, 2.0, -1.5, 0.0, 0.0, -0.5, 0.5,
+            ],
+            BasisType::Taylor => (0..(degree + 1) * (degree + 1))
+                .map(|i| if i % (degree + 2) == 0 { 1.0 } else { 0.0 })
+                .collect(),
+            BasisType::BasisMatrix
+                if self.matrix[direction].len() == (degree + 1) * (degree + 1) =>
+            {
+                self.matrix[direction].clone()
+            }
+            _ => return None,
+        };
+        let weights = matrix
+            .chunks(degree + 1)
+            .map(|coefficients| {
+                coefficients
+                    .iter()
+                    .rev()
+                    .fold(0.0, |weight, coefficient| weight * s + coefficient)
+            })
+            .collect();
+        Some((segment * self.step_size(direction)?, weights))
+    }
+}
+
+impl Curve2D {
+    /// Evaluate the curve at parameter `t`, returning the point in the
+    /// parameter space of the surface.
+    fn evaluate(&self, t: Float) -> Option<[Float; 2]> {
+        if self.basis.control_point_count(0, &self.knots)? != self.control_points.len() {
+            return None;
+        }
+        let (first, weights) = self.basis.weights(0, &self.knots, t)?;
+        let mut point = [0.0; 3];
+        for (p, weight) in self.control_points[first..].iter().zip(weights) {
+            let w = if self.basis.rational {
+                p[2] * weight
+            } else {
+                weight
+            };
+            point = add(point, [p[0] * w, p[1] * w, w]);
+        }
+        let denominator = if self.basis.rational { point[2] } else { 1.0 };
+        (denominator != 0.0).then(|| [point[0] / denominator, point[1] / denominator])
+    }
+}
+
+/// Approximate a trimming loop by a polygon in the parameter space of the
+/// surface, with `resolution` segments per curve segment.
+fn trimming_polygon(segments: &[CurveSegment], resolution: u32) -> Option<Vec<[Float; 2]>> {
+    let mut polygon = Vec::new();
+    for segment in segments {
+        let [start, end] = segment.parameter_range;
+        for i in 0..=resolution {
+            let t = start + (end - start) * i as Float / resolution as Float;
+            polygon.push(segment.curve.evaluate(t)?);
+        }
+    }
+    Some(polygon)
+}
+
+/// Whether `point` is inside `polygon`, using the even-odd rule.
+fn polygon_contains(polygon: &[[Float; 2]], point: [Float; 2]) -> bool {
+    let mut inside = false;
+    for (a, b) in polygon.iter().zip(polygon.iter().cycle().skip(1)) {
+        if (a[1] > point[1]) != (b[1] > point[1])
+            && point[0] < a[0] + (point[1] - a[1]) / (b[1] - a[1]) * (b[0] - a[0])
+        {
+            inside = !inside;
+        }
+    }
+    inside
+}
+
+impl Surface {
+    /// Tessellate the surface into a triangle [`Mesh`].
+    ///
+    /// The parameter range of the surface is split into `resolution` segments
+    /// in both u and v direction and every resulting grid cell into two
+    /// triangles. Triangles are kept if their center lies within the trimming
+    /// loops and outside of the holes, each curve segment of which is
+    /// approximated with `resolution` segments as well. Trimmed edges are
+    /// therefore only as accurate as the resolution.
+    ///
+    /// The mesh uses a single index. It has normals, interpolated from those
+    /// of the control points if any, and texture coordinates if the control
+    /// points have them. Triangles without area, e.g. at the poles of a
+    /// sphere, are left out.
+    ///
+    /// Returns `None` if the surface can't be evaluated, e.g. because the
+    /// number of control points doesn't match its basis and knot vectors or
+    /// the knots are not finite and non-decreasing.
+    pub fn tessellate(&self, resolution: u32) -> Option<Mesh> {
+        let resolution = resolution.max(1);
+        let nu = self.basis.control_point_count(0, &self.knots[0])?;
+        let nv = self.basis.control_point_count(1, &self.knots[1])?;
+        if nu.checked_mul(nv) != Some(self.control_points.len()) {
+            return None;
+        }
+        let params = |direction: usize| {
+            let [start, end] = self.parameter_range[direction];
+            (0..=resolution)
+                .map(|i| {
+                    let t = start + (end - start) * i as Float / resolution as Float;
+                    let (first, weights) =
+                        self.basis.weights(direction, &self.knots[direction], t)?;
+                    let n = if direction == 0 { nu } else { nv };
+                    (first + weights.len() <= n).then_some((t, first, weights))
+                })
+                .collect::<Option<Vec<_>>>()
+        };
+        let (u_params, v_params) = (params(0)?, params(1)?);
+
+        // Evaluate the grid of vertices, u varying fastest like the control
+        // points
+        let mut mesh = Mesh::default();
+        let mut uvs = Vec::new();
+        for (v, v_first, v_weights) in &v_params {
+            for (u, u_first, u_weights) in &u_params {
+                let mut position = [0.0; 4];
+                let mut texcoord = [0.0; 2];
+                let mut normal = [0.0; 3];
+                for (j, v_weight) in v_weights.iter().enumerate() {
+                    for (i, u_weight) in u_weights.iter().enumerate() {
+                        let index = (v_first + j) * nu + u_first + i;
+                        let p = self.control_points[index];
+                        let w = u_weight * v_weight * if self.basis.rational { p[3] } else { 1.0 };
+                        position = [
+                            position[0] + p[0] * w,
+                            position[1] + p[1] * w,
+                            position[2] + p[2] * w,
+                            position[3] + w,
+                        ];
+                        if let Some(t) = self.texcoords.get(index) {
+                            texcoord = [texcoord[0] + t[0] * w, texcoord[1] + t[1] * w];
+                        }
+                        if let Some(&n) = self.normals.get(index) {
+                            normal = add(normal, scale(n, w));
+                        }
+                    }
+                }
+                let denominator = if self.basis.rational {
+                    position[3]
+                } else {
+                    1.0
+                };
+                if denominator == 0.0 {
+                    return None;
+                }
+                mesh.positions.extend(
+                    position[..3]
+                        .iter()
+                        .map(|coordinate| coordinate / denominator),
+                );
+                if !self.texcoords.is_empty() {
+                    mesh.texcoords.push(texcoord[0] / denominator);
+                    mesh.texcoords.push(texcoord[1] / denominator);
+                }
+                if !self.normals.is_empty() {
+                    mesh.normals.extend(normalize(normal));
+                }
+                uvs.push([*u, *v]);
+            }
+        }
+
+        let width = resolution as usize + 1;
+        let position = |i: usize, j: usize| {
+            let index = (j * width + i) * 3;
+            [
+                mesh.positions[index],
+                mesh.positions[index + 1],
+                mesh.positions[index + 2],
+            ]
+        };
+        if self.normals.is_empty() {
+            // Normals from the partial derivatives of the grid
+            let mut normals = Vec::with_capacity(mesh.positions.len());
+            for j in 0..width {
+                for i in 0..width {
+                    let du = sub(
+                        position((i + 1).min(width - 1), j),
+                        position(i.saturating_sub(1), j),
+                    );
+                    let dv = sub(
+                        position(i, (j + 1).min(width - 1)),
+                        position(i, j.saturating_sub(1)),
+                    );
+                    normals.extend(normalize(cross(du, dv)));
+                }
+            }
+            mesh.normals = normals;
+        }
+
+        let trims = self
+            .trims
+            .iter()
+            .map(|segments| trimming_polygon(segments, resolution))
+            .collect::<Option<Vec<_>>>()?;
+        let holes = self
+            .holes
+            .iter()
+            .map(|segments| trimming_polygon(segments, resolution))
+            .collect::<Option<Vec<_>>>()?;
+        let mut indices = Vec::new();
+        for j in 0..width - 1 {
+            for i in 0..width - 1 {
+                let corners = [
+                    (i, j),
+                    (i + 1, j),
+                    (i + 1, j + 1),
+                    (i, j),
+                    (i + 1, j + 1),
+                    (i, j + 1),
+                ];
+                for triangle in corners.chunks(3) {
+                    let area = polygon_normal(triangle.iter().map(|&(i, j)| position(i, j)));
+                    if area == [0.0; 3] {
+                        continue;
+                    }
+                    let center = triangle.iter().fold([0.0; 2], |center, &(i, j)| {
+                        let uv = uvs[j * width + i];
+                        [center[0] + uv[0] / 3.0, center[1] + uv[1] / 3.0]
+                    });
+                    if (trims.is_empty() || trims.iter().any(|trim| polygon_contains(trim, center)))
+                        && !holes.iter().any(|hole| polygon_contains(hole, center))
+                    {
+                        indices.extend(triangle.iter().map(|&(i, j)| (j * width + i) as u32));
+                    }
+                }
+            }
+        }
+
+        // Remove the vertices of trimmed triangles, keeping the order of the
+        // grid
+        let mut index_map = vec![u32::MAX; uvs.len()];
+        indices
+            .iter()
+            .for_each(|&index| index_map[index as usize] = 0);
+        let mut compacted = Mesh::default();
+        for (vertex, new_index) in index_map.iter_mut().enumerate() {
+            if *new_index == u32::MAX {
+                continue;
+            }
+            *new_index = (compacted.positions.len() / 3) as u32;
+            compacted
+                .positions
+                .extend_from_slice(&mesh.positions[vertex * 3..vertex * 3 + 3]);
+            compacted
+                .normals
+                .extend_from_slice(&mesh.normals[vertex * 3..vertex * 3 + 3]);
+            if !mesh.texcoords.is_empty() {
+                compacted
+                    .texcoords
+                    .extend_from_slice(&mesh.texcoords[vertex * 2..vertex * 2 + 2]);
+            }
+        }
+        indices
+            .iter_mut()
+            .for_each(|index| *index = index_map[*index as usize]);
+        compacted.indices = indices;
+        Some(compacted)
+    }
+}
+
+/// A material that may be referenced by one or more [`Mesh`]es.
+///
+/// Standard `MTL` attributes are supported. Any unrecognized parameters will be
+/// stored as key-value pairs in the `unknown_param`
+/// [`HashMap`](std::collections::HashMap), which maps the unknown parameter to
+/// the value set for it.
+///
+/// No path is pre-pended to the texture file names specified in the `MTL` file.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Material {
+    /// Material name as specified in the `MTL` file.
+    pub name: String,
+    /// Ambient color of the material.
+    pub ambient: Option<[Float; 3]>,
+    /// Diffuse color of the material.
+    pub diffuse: Option<[Float; 3]>,
+    /// Specular color of the material.
+    pub specular: Option<[Float; 3]>,
+    /// Emissive color of the material.
+    pub emissive: Option<[Float; 3]>,
+    /// Material shininess attribute. Also called `glossiness`.
+    pub shininess: Option<Float>,
+    /// Dissolve attribute is the alpha term for the material. Referred to as
+    /// dissolve since that's what the `MTL` file format docs refer to it as.
+    pub dissolve: Option<Float>,
+    /// Optical density also known as index of refraction. Called
+    /// `optical_density` in the `MTL` specc. Takes on a value between 0.001
+    /// and 10.0. 1.0 means light does not bend as it passes through
+    /// the object.
+    pub optical_density: Option<Float>,
+    /// Ambient texture map of the material.
+    pub ambient_texture: Option<TextureMap>,
+    /// Diffuse texture map of the material.
+    pub diffuse_texture: Option<TextureMap>,
+    /// Specular texture map of the material.
+    pub specular_texture: Option<TextureMap>,
+    /// Normal texture map of the material, specified as a bump map (`bump` or
+    /// `map_Bump`).
+    pub normal_texture: Option<TextureMap>,
+    /// Shininess texture map of the material.
+    pub shininess_texture: Option<TextureMap>,
+    /// Alpha/opacity texture map of the material.
+    ///
+    /// Referred to as `dissolve` to match the `MTL` file format specification.
+    pub dissolve_texture: Option<TextureMap>,
+    /// The illumnination model to use for this material. The different
+    /// illumination models are specified in the [`MTL` spec](http://paulbourke.net/dataformats/mtl/).
+    pub illumination_model: Option<u8>,
+    /// Roughness of the material (`Pr`). Part of the PBR extension.
+    pub roughness: Option<Float>,
+    /// Metallic attribute of the material (`Pm`). Part of the PBR extension.
+    pub metallic: Option<Float>,
+    /// Sheen of the material (`Ps`). Part of the PBR extension.
+    pub sheen: Option<Float>,
+    /// Thickness of the clearcoat layer of the material (`Pc`). Part of the
+    /// PBR extension.
+    pub clearcoat_thickness: Option<Float>,
+    /// Roughness of the clearcoat layer of the material (`Pcr`). Part of the
+    /// PBR extension.
+    pub clearcoat_roughness: Option<Float>,
+    /// Anisotropy of the material (`aniso`). Part of the PBR extension.
+    pub anisotropy: Option<Float>,
+    /// Rotation of the anisotropy of the material (`anisor`). Part of the PBR
+    /// extension.
+    pub anisotropy_rotation: Option<Float>,
+    /// Roughness texture map of the material (`map_Pr`). Part of the PBR
+    /// extension.
+    pub roughness_texture: Option<TextureMap>,
+    /// Metallic texture map of the material (`map_Pm`). Part of the PBR
+    /// extension.
+    pub metallic_texture: Option<TextureMap>,
+    /// Sheen texture map of the material (`map_Ps`). Part of the PBR
+    /// extension.
+    pub sheen_texture: Option<TextureMap>,
+    /// Emissive texture map of the material (`map_Ke`). Part of the PBR
+    /// extension.
+    pub emissive_texture: Option<TextureMap>,
+    /// Normal texture map of the material (`norm`). Part of the PBR extension.
+    ///
+    /// Unlike the bump map in [`normal_texture`](Material::normal_texture)
+    /// this is always a tangent space normal map.
+    pub normal_map_texture: Option<TextureMap>,
+    /// Key value pairs of any unrecognized parameters encountered while parsing
+    /// the material.
+    pub unknown_param: HashMap<String, String>,
+}
+
+impl Material {
+    /// The texture maps of the material along with the keyword they are
+    /// written with.
+    fn texture_maps(&self) -> [(&'static str, &Option<TextureMap>); 11] {
+        [
+            ("map_Ka", &self.ambient_texture),
+            ("map_Kd", &self.diffuse_texture),
+            ("map_Ks", &self.specular_texture),
+            ("map_Bump", &self.normal_texture),
+            ("map_Ns", &self.shininess_texture),
+            ("map_d", &self.dissolve_texture),
+            ("map_Pr", &self.roughness_texture),
+            ("map_Pm", &self.metallic_texture),
+            ("map_Ps", &self.sheen_texture),
+            ("map_Ke", &self.emissive_texture),
+            ("norm", &self.normal_map_texture),
+        ]
+    }
+}
+
+/// A texture map of a [`Material`] along with the options it was given in the
+/// `MTL` file.
+///
+/// E.g. `map_Kd -s 2 2 1 -clamp on wood.png` has the [`path`](TextureMap::path)
+/// `wood.png`, a [`scale`](TextureMap::scale) of `[2, 2, 1]` and
+/// [`clamp`](TextureMap::clamp) enabled. Options not specified in the file are
+/// `None`. The meaning of each option is described in the
+/// [`MTL` spec](http://paulbourke.net/dataformats/mtl/).
+///
+/// Options with invalid values are ignored when loading a `MTL` file with a
+/// [`LoadWarning::InvalidTextureOptions`], while parsing a `TextureMap` from a
+/// string fails on them.
+///
+/// The last word of the statement is always taken as the file name, or the end
+/// of it, even if it follows an option missing its value.
+///
+/// Formatting a `TextureMap` via [`Display`](fmt::Display) gives its options
+/// followed by its path, as they would be written in a `MTL` file.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TextureMap {
+    /// Name of the texture file. No path is pre-pended to it.
+    pub path: String,
+    /// The texture map statement as it was specified in the `MTL` file, options
+    /// included. It is not used when writing the texture map.
+    pub raw: String,
+    /// Whether horizontal texture blending is enabled (`-blendu`).
+    pub blend_u: Option<bool>,
+    /// Whether vertical texture blending is enabled (`-blendv`).
+    pub blend_v: Option<bool>,
+    /// Multiplier for the values of a bump map (`-bm`).
+    pub bump_multiplier: Option<Float>,
+    /// Boost for the sharpness of mip-mapped textures (`-boost`).
+    pub boost: Option<Float>,
+    /// Whether color correction is enabled (`-cc`).
+    pub color_correction: Option<bool>,
+    /// Whether texture coordinates are clamped to `[0, 1]` (`-clamp`).
+    pub clamp: Option<bool>,
+    /// The channel of the texture used for scalar or bump maps (`-imfchan`).
+    /// One of `r`, `g`, `b`, `m`, `l` or `z`.
+    pub imfchan: Option<char>,
+    /// Base value and gain the texture values are modified with (`-mm`).
+    pub mm: Option<[Float; 2]>,
+    /// Offset of the texture origin (`-o`).
+    pub offset: Option<[Float; 3]>,
+    /// Scale of the texture pattern (`-s`).
+    pub scale: Option<[Float; 3]>,
+    /// Turbulence applied to the texture (`-t`).
+    pub turbulence: Option<[Float; 3]>,
+    /// Resolution of the texture created from an image (`-texres`).
+    pub texture_resolution: Option<u32>,
+    /// The type of reflection map, e.g. `sphere` or `cube_top` (`-type`).
+    pub texture_type: Option<String>,
+}
+
+impl FromStr for TextureMap {
+    type Err = LoadError;
+
+    /// Parse a texture map from the options and file name following a texture
+    /// map keyword in a `MTL` file.
+    fn from_str(raw: &str) -> Result<TextureMap, LoadError> {
+        match TextureMap::parse_lenient(raw)? {
+            (texture, true) => Ok(texture),
+            (_, false) => Err(LoadErrorKind::MaterialParseError.into()),
+        }
+    }
+}
+
+impl TextureMap {
+    /// Parse a texture map like [`from_str`](TextureMap::from_str), but skip
+    /// options with invalid values instead of failing. Also returns whether all
+    /// options were valid.
+    fn parse_lenient(raw: &str) -> Result<(TextureMap, bool), LoadError> {
+        let mut texture = TextureMap {
+            raw: raw.trim().to_owned(),
+            ..Default::default()
+        };
+
+        // Split off the next word, leaving the rest as it is as file names may
+        // contain spaces.
+        fn next_word<'a>(rest: &mut &'a str) -> Option<&'a str> {
+            let trimmed = rest.trim_start();
+            let end = trimmed.find(char::is_whitespace).unwrap_or(trimmed.len());
+            *rest = &trimmed[end..];
+            Some(&trimmed[..end]).filter(|word| !word.is_empty())
+        }
+        fn on_off(rest: &mut &str) -> Result<bool, LoadError> {
+            match next_word(rest) {
+                Some("on") => Ok(true),
+                Some("off") => Ok(false),
+                _ => Err(LoadErrorKind::MaterialParseError.into()),
+            }
+        }
+        fn number<T: FromStr>(rest: &mut &str) -> Result<T, LoadError> {
+            next_word(rest)
+                .and_then(|word| word.parse().ok())
+                .ok_or_else(|| LoadErrorKind::MaterialParseError.into())
+        }
+        // One to three values, the missing ones take the default.
+        fn uvw(rest: &mut &str, default: Float) -> Result<[Float; 3], LoadError> {
+            let mut uvw = [number(rest)?, default, default];
+            for value in &mut uvw[1..] {
+                let mut next = *rest;
+                match number(&mut next) {
+                    Ok(x) => {
+                        *value = x;
+                        *rest = next;
+                    }
+                    Err(_) => break,
+                }
+            }
+            Ok(uvw)
+        }
+
+        // The last word is always part of the file name, so an option missing
+        // its value doesn't take the file name instead.
+        let raw = raw.trim();
+        let options = &raw[..raw.rfind(char::is_whitespace).unwrap_or(0)];
+        let mut rest = options;
+        let mut valid = true;
+        loop {
+            let mut next = rest;
+            // An invalid value is skipped along with its option
+            let option = match next_word(&mut next) {
+                Some("-blendu") => on_off(&mut next).map(|on| texture.blend_u = Some(on)),
+                Some("-blendv") => on_off(&mut next).map(|on| texture.blend_v = Some(on)),
+                Some("-bm") => number(&mut next).map(|x| texture.bump_multiplier = Some(x)),
+                Some("-boost") => number(&mut next).map(|x| texture.boost = Some(x)),
+                Some("-cc") => on_off(&mut next).map(|on| texture.color_correction = Some(on)),
+                Some("-clamp") => on_off(&mut next).map(|on| texture.clamp = Some(on)),
+                Some("-imfchan") => number(&mut next).map(|c| texture.imfchan = Some(c)),
+                Some("-mm") => number(&mut next)
+                    .and_then(|base| Ok([base, number(&mut next)?]))
+                    .map(|mm| texture.mm = Some(mm)),
+                Some("-o") => uvw(&mut next, 0.0).map(|o| texture.offset = Some(o)),
+                Some("-s") => uvw(&mut next, 1.0).map(|s| texture.scale = Some(s)),
+                Some("-t") => uvw(&mut next, 0.0).map(|t| texture.turbulence = Some(t)),
+                Some("-texres") => {
+                    number(&mut next).map(|res| texture.texture_resolution = Some(res))
+                }
+                Some("-type") => next_word(&mut next)
+                    .map(|texture_type| texture.texture_type = Some(texture_type.to_owned()))
+                    .ok_or_else(|| LoadErrorKind::MaterialParseError.into()),
+                // Anything else is the start of the file name.
+                _ => break,
+            };
+            valid &= option.is_ok();
+            rest = next;
+        }
+
+        texture.path = raw[options.len() - rest.len()..].trim().to_owned();
+        if texture.path.is_empty() {
+            return Err(LoadErrorKind::MaterialParseError.into());
+        }
+        Ok((texture, valid))
+    }
+}
+
+impl fmt::Display for TextureMap {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        let on_off = |enabled: bool| if enabled { "on" } else { "off" };
+        if let Some(blend_u) = self.blend_u {
+            write!(f, "-blendu {} ", on_off(blend_u))?;
+        }
+        if let Some(blend_v) = self.blend_v {
+            write!(f, "-blendv {} ", on_off(blend_v))?;
+        }
+        if let Some(bump_multiplier) = self.bump_multiplier {
+            write!(f, "-bm {} ", bump_multiplier)?;
+        }
+        if let Some(boost) = self.boost {
+            write!(f, "-boost {} ", boost)?;
+        }
+        if let Some(color_correction) = self.color_correction {
+            write!(f, "-cc {} ", on_off(color_correction))?;
+        }
+        if let Some(clamp) = self.clamp {
+            write!(f, "-clamp {} ", on_off(clamp))?;
+        }
+        if let Some(imfchan) = self.imfchan {
+            write!(f, "-imfchan {} ", imfchan)?;
+        }
+        if let Some([base, gain]) = self.mm {
+            write!(f, "-mm {} {} ", base, gain)?;
+        }
+        let uvws = [
+            ("-o", self.offset),
+            ("-s", self.scale),
+            ("-t", self.turbulence),
+        ];
+        for (option, uvw) in uvws {
+            if let Some([u, v, w]) = uvw {
+                write!(f, "{} {} {} {} ", option, u, v, w)?;
+            }
+        }
+        if let Some(texture_resolution) = self.texture_resolution {
+            write!(f, "-texres {} ", texture_resolution)?;
+        }
+        if let Some(texture_type) = &self.texture_type {
+            write!(f, "-type {} ", texture_type)?;
+        }
+        f.write_str(&self.path)
+    }
+}
+
+/// The kinds of errors that may occur while loading `OBJ` and `MTL` files.
+///
+/// See [`LoadError::kind()`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LoadErrorKind {
+    OpenFileFailed,
+    ReadError,
+    UnrecognizedCharacter,
+    PositionParseError,
+    NormalParseError,
+    TexcoordParseError,
+    FaceParseError,
+    MaterialParseError,
+    InvalidObjectName,
+    InvalidPolygon,
+    FaceVertexOutOfBounds,
+    FaceTexCoordOutOfBounds,
+    FaceNormalOutOfBounds,
+    FaceColorOutOfBounds,
+    InvalidLoadOptionConfig,
+    FreeFormParseError,
+    GenericFailure,
+}
+
+impl fmt::Display for LoadErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        let msg = match *self {
+            LoadErrorKind::OpenFileFailed => "open file failed",
+            LoadErrorKind::ReadError => "read error",
+            LoadErrorKind::UnrecognizedCharacter => "unrecognized character",
+            LoadErrorKind::PositionParseError => "position parse error",
+            LoadErrorKind::NormalParseError => "normal parse error",
+            LoadErrorKind::TexcoordParseError => "texcoord parse error",
+            LoadErrorKind::FaceParseError => "face parse error",
+            LoadErrorKind::MaterialParseError => "material parse error",
+            LoadErrorKind::InvalidObjectName => "invalid object name",
+            LoadErrorKind::InvalidPolygon => "invalid polygon",
+            LoadErrorKind::FaceVertexOutOfBounds => "face vertex index out of bounds",
+            LoadErrorKind::FaceTexCoordOutOfBounds => "face texcoord index out of bounds",
+            LoadErrorKind::FaceNormalOutOfBounds => "face normal index out of bounds",
+            LoadErrorKind::FaceColorOutOfBounds => "face vertex color index out of bounds",
+            LoadErrorKind::InvalidLoadOptionConfig => "mutually exclusive load options",
+            LoadErrorKind::FreeFormParseError => "free-form geometry parse error",
+            LoadErrorKind::GenericFailure => "generic failure",
+        };
+
+        f.write_str(msg)
+    }
+}
+
+/// An error that occurred while loading `OBJ` and `MTL` files.
+///
+/// Besides its [`kind()`](LoadError::kind), the error records where it
+/// occurred as far as this is known: the line number and directive or token
+/// in the file as well as the path of the file. Errors caused by IO failures
+/// carry the underlying [`std::io::Error`] as their
+/// [`source()`](Error::source).
+///
+/// Errors compare equal to their [`LoadErrorKind`], e.g.
+///
+/// ```
+/// let obj = "v 0 0 0\nf 1 2 3\n";
+/// let err = tobj::load_obj_buf(
+///     &mut obj.as_bytes(),
+///     &tobj::LoadOptions::default(),
+///     |_| unreachable!(),
+/// )
+/// .unwrap_err();
+/// assert_eq!(err, tobj::LoadErrorKind::FaceVertexOutOfBounds);
+/// assert_eq!(err.line(), Some(2));
+/// ```
+#[derive(Debug, Clone)]
+pub struct LoadError {
+    kind: LoadErrorKind,
+    line: Option<usize>,
+    token: Option<String>,
+    path: Option<PathBuf>,
+    io_error: Option<Arc<std::io::Error>>,
+}
+
+impl LoadError {
+    /// Create an error of the given kind without any further information.
+    pub fn new(kind: LoadErrorKind) -> Self {
+        Self {
+            kind,
+            line: None,
+            token: None,
+            path: None,
+            io_error: None,
+        }
+    }
+
+    /// The kind of error.
+    pub fn kind(&self) -> LoadErrorKind {
+        self.kind
+    }
+
+    /// The line the error occurred at, starting at 1.
+    pub fn line(&self) -> Option<usize> {
+        self.line
+    }
+
+    /// The directive, e.g. `usemtl`, or the token within its line that caused
+    /// the error.
+    pub fn token(&self) -> Option<&str> {
+        self.token.as_deref()
+    }
+
+    /// The path of the `OBJ` or `MTL` file the error occurred in.
+    ///
+    /// Only known when loading from a file, e.g. with [`load_obj()`] or
+    /// [`load_mtl()`]. Errors of material libraries loaded while loading an
+    /// `OBJ` file carry the path of the `MTL` file.
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// The IO error that caused an
+    /// [`OpenFileFailed`](LoadErrorKind::OpenFileFailed) or
+    /// [`ReadError`](LoadErrorKind::ReadError).
+    pub fn io_error(&self) -> Option<&std::io::Error> {
+        self.io_error.as_deref()
+    }
+
+    /// Set the line and token the error occurred at, unless the line is
+    /// already known.
+    fn at_line(mut self, line: usize, token: Option<&str>) -> Self {
+        if self.line.is_none() {
+            self.line = Some(line);
+            if self.token.is_none() {
+                self.token = token.map(ToOwned::to_owned);
+            }
+        }
+        self
+    }
+
+    fn with_token(mut self, token: &str) -> Self {
+        self.token = Some(token.to_owned());
+        self
+    }
+
+    /// Set the path of the file the error occurred in, unless it is already
+    /// known.
+    fn in_file(mut self, path: &Path) -> Self {
+        if self.path.is_none() {
+            self.path = Some(path.to_owned());
+        }
+        self
+    }
+
+    fn with_io_error(mut self, io_error: std::io::Error) -> Self {
+        self.io_error = Some(Arc::new(io_error));
+        self
+    }
+}
+
+impl From<LoadErrorKind> for LoadError {
+    fn from(kind: LoadErrorKind) -> Self {
+        Self::new(kind)
+    }
+}
+
+impl PartialEq for LoadError {
+    fn eq(&self, other: &Self) -> bool {
+        self.kind == other.kind
+            && self.line == other.line
+            && self.token == other.token
+            && self.path == other.path
+            && self.io_error().map(std::io::Error::kind)
+                == other.io_error().map(std::io::Error::kind)
+    }
+}
+
+impl PartialEq<LoadErrorKind> for LoadError {
+    fn eq(&self, kind: &LoadErrorKind) -> bool {
+        self.kind == *kind
+    }
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(f, "{}", self.kind)?;
+        if let Some(line) = self.line {
+            write!(f, " at line {line}")?;
+        }
+        if let Some(path) = &self.path {
+            write!(f, " in {}", path.display())?;
+        }
+        if let Some(token) = &self.token {
+            write!(f, " near `{token}`")?;
+        }
+        if let Some(io_error) = &self.io_error {
+            write!(f, ": {io_error}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for LoadError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        self.io_error.as_deref().map(|e| e as _)
+    }
+}
+
+/// A problem found while loading `OBJ` and `MTL` files that did not keep them
+/// from loading.
+#[derive(Debug, Clone, PartialEq)]
+pub enum LoadWarning {
+    /// A line that failed to parse was skipped, see
+    /// [`skip_invalid_lines`](LoadOptions::skip_invalid_lines).
+    SkippedLine(LoadError),
+    /// An object refers to a material not found in any material library.
+    UnresolvedMaterial {
+        line: usize,
+        object: String,
+        material: String,
+    },
+    /// A directive tobj does not support was ignored.
+    IgnoredDirective { line: usize, directive: String },
+    /// A point was discarded, see
+    /// [`ignore_points`](LoadOptions::ignore_points).
+    DiscardedPoint { line: usize },
+    /// A line was discarded, see [`ignore_lines`](LoadOptions::ignore_lines).
+    DiscardedLine { line: usize },
+    /// A face has no area, e.g. as all its vertices are collinear. It is
+    /// loaded nonetheless.
+    DegenerateFace { line: usize },
+    /// An `s` statement has no or an invalid smoothing group. The following
+    /// faces are not in any smoothing group, like after `s off`.
+    InvalidSmoothingGroup { line: usize },
+    /// A free-form surface could not be tessellated, see
+    /// [`tessellate_surfaces`](LoadOptions::tessellate_surfaces).
+    UntessellatedSurface { line: usize },
+    /// A material has a parameter tobj does not know. It is stored in the
+    /// [`unknown_param`](Material::unknown_param)s of the material.
+    UnknownMaterialParameter { material: String, key: String },
+    /// A texture map of a material has options with invalid values. They are
+    /// ignored, but kept in the [`raw`](TextureMap::raw) statement of the
+    /// texture map.
+    InvalidTextureOptions { material: String, keyword: String },
+}
+
+impl LoadWarning {
+    /// The line of the `OBJ` file the warning refers to, starting at 1.
+    ///
+    /// Warnings about materials don't refer to a line of the `OBJ` file.
+    pub fn line(&self) -> Option<usize> {
+        match *self {
+            LoadWarning::SkippedLine(ref e) => e.line(),
+            LoadWarning::UnresolvedMaterial { line, .. }
+            | LoadWarning::IgnoredDirective { line, .. }
+            | LoadWarning::DiscardedPoint { line }
+            | LoadWarning::DiscardedLine { line }
+            | LoadWarning::DegenerateFace { line }
+            | LoadWarning::InvalidSmoothingGroup { line }
+            | LoadWarning::UntessellatedSurface { line } => Some(line),
+            LoadWarning::UnknownMaterialParameter { .. }
+            | LoadWarning::InvalidTextureOptions { .. } => None,
+        }
+    }
+}
+
+impl fmt::Display for LoadWarning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match self {
+            LoadWarning::SkippedLine(e) => write!(f, "skipped line: {e}"),
+            LoadWarning::UnresolvedMaterial {
+                line,
+                object,
+                material,
+            } => write!(
+                f,
+                "object {object} refers to unfound material {material} at line {line}"
+            ),
+            LoadWarning::IgnoredDirective { line, directive } => {
+                write!(f, "ignored directive `{directive}` at line {line}")
+            }
+            LoadWarning::DiscardedPoint { line } => write!(f, "discarded point at line {line}"),
+            LoadWarning::DiscardedLine { line } => write!(f, "discarded line at line {line}"),
+            LoadWarning::DegenerateFace { line } => write!(f, "degenerate face at line {line}"),
+            LoadWarning::InvalidSmoothingGroup { line } => {
+                write!(f, "invalid smoothing group at line {line}")
+            }
+            LoadWarning::UntessellatedSurface { line } => {
+                write!(f, "surface at line {line} could not be tessellated")
+            }
+            LoadWarning::UnknownMaterialParameter { material, key } => {
+                write!(f, "unknown parameter `{key}` of material {material}")
+            }
+            LoadWarning::InvalidTextureOptions { material, keyword } => {
+                write!(f, "invalid options of `{keyword}` of material {material}")
+            }
+        }
+    }
+}
+
+/// A [`Result`] containing all the models loaded from the file, any materials
+/// from referenced material libraries and the warnings found while loading. Or
+/// an error that occurred while loading.
+pub type LoadResult = Result<
+    (
+        Vec<Model>,
+        Result<Vec<Material>, LoadError>,
+        Vec<LoadWarning>,
+    ),
+    LoadError,
+>;
+
+/// A [`Result`] containing the geometry of the file, any materials from
+/// referenced material libraries and the warnings found while loading. Or an
+/// error that occurred while loading.
+pub type RawLoadResult =
+    Result<(RawObj, Result<Vec<Material>, LoadError>, Vec<LoadWarning>), LoadError>;
+
+/// A [`Result`] containing all the materials loaded from the file, a map of
+/// `MTL` name to index and the warnings found while loading. Or an error that
+/// occurred while loading.
+pub type MTLLoadResult =
+    Result<(Vec<Material>, HashMap<String, usize>, Vec<LoadWarning>), LoadError>;
+
+/// Struct storing indices corresponding to the vertex.
+///
+/// The indices start at 0, unlike the indices in the `OBJ` file which start at
+/// 1. Relative (negative) indices in the file are resolved.
+///
+/// Some vertices may not have texture coordinates or normals, [`MISSING_INDEX`]
+/// is used to indicate this.
+#[derive(Hash, Eq, PartialEq, PartialOrd, Ord, Debug, Copy, Clone)]
+pub struct VertexIndices {
+    /// Index of the position, and vertex color if any.
+    pub v: usize,
+    /// Index of the texture coordinate.
+    pub vt: usize,
+    /// Index of the normal.
+    pub vn: usize,
+}
+
+/// Index used in [`VertexIndices`] for a texture coordinate or normal that is
+/// missing.
+pub const MISSING_INDEX: usize = usize::MAX;
+
+/// Name of the material the writer resets the material to with a `usemtl`
+/// statement. Unless a material library defines it, it is no material, without
+/// a [`LoadWarning::UnresolvedMaterial`].
+const NO_MATERIAL: &str = "(null)";
+
+impl VertexIndices {
+    /// Parse the vertex indices from the face string.
+    ///
+    /// Valid face strings are those that are valid for a Wavefront `OBJ` file.
+    ///
+    /// Also handles relative face indices (negative values) which is why
+    /// passing the number of positions, texcoords and normals is required.
+    ///
+    /// Returns `None` if the face string is invalid.
+    fn parse(
+        face_str: &[u8],
         pos_sz: usize,
         tex_sz: usize,
         norm_sz: usize,
     ) -> Option<VertexIndices> {
         let mut indices = [MISSING_INDEX; 3];
-        for i in face_str.split('/').enumerate() {
+        for i in face_str.split(|byte| *byte == b'/').enumerate() {
             // Catch case of v//vn where we'll find an empty string in one of our splits
             // since there are no texcoords for the mesh.
             if !i.1.is_empty() {
-                match isize::from_str(i.1) {
-                    Ok(x) => {
-                        // Handle relative indices
-                        *indices.get_mut(i.0)? = if x < 0 {
-                            match i.0 {
-                                0 => (pos_sz as isize + x) as _,
-                                1 => (tex_sz as isize + x) as _,
-                                2 => (norm_sz as isize + x) as _,
-                                _ => return None, // Invalid number of elements for a face
-                            }
-                        } else {
-                            (x - 1) as _
-                        };
+                let x = parse_index_bytes(i.1)?;
+                // Handle relative indices
+                *indices.get_mut(i.0)? = if x < 0 {
+                    match i.0 {
+                        0 => (pos_sz as isize + x) as _,
+                        1 => (tex_sz as isize + x) as _,
+                        2 => (norm_sz as isize + x) as _,
+                        _ => return None, // Invalid number of elements for a face
                     }
-                    Err(_) => return None,
-                }
+                } else {
+                    (x - 1) as _
+                };
             }
         }
         Some(VertexIndices {
@@ -754,18 +2732,214 @@ enum Face {
     Polygon(Vec<VertexIndices>),
 }
 
-/// Parse the float information from the words. Words is an iterator over the
-/// float strings. Returns `false` if parsing failed.
-fn parse_floatn(val_str: &mut SplitWhitespace, vals: &mut Vec<Float>, n: usize) -> bool {
-    let sz = vals.len();
-    for p in val_str.take(n) {
-        match FromStr::from_str(p) {
-            Ok(x) => vals.push(x),
-            Err(_) => return false,
+impl Face {
+    /// The vertices of the face in order.
+    fn vertices(&self) -> Vec<VertexIndices> {
+        match *self {
+            Face::Point(a) => vec![a],
+            Face::Line(a, b) => vec![a, b],
+            Face::Triangle(a, b, c) => vec![a, b, c],
+            Face::Quad(a, b, c, d) => vec![a, b, c, d],
+            Face::Polygon(ref indices) => indices.clone(),
         }
     }
-    // Require that we found the desired number of floats.
-    sz + n == vals.len()
+
+    /// Whether the face has no area, e.g. as all its vertices are collinear.
+    /// Points and lines are not degenerate.
+    ///
+    /// The indices of the vertices must be in bounds of `pos`.
+    fn is_degenerate(&self, pos: &[Float]) -> bool {
+        let position = |v: &VertexIndices| [pos[v.v * 3], pos[v.v * 3 + 1], pos[v.v * 3 + 2]];
+        let normal = match self {
+            Face::Point(_) | Face::Line(..) => return false,
+            Face::Triangle(a, b, c) => polygon_normal([a, b, c].into_iter().map(position)),
+            Face::Quad(a, b, c, d) => polygon_normal([a, b, c, d].into_iter().map(position)),
+            Face::Polygon(indices) => polygon_normal(indices.iter().map(position)),
+        };
+        normal == [0.0; 3]
+    }
+
+    /// Check that the indices of all vertices of the face are in bounds of
+    /// the vertex data they are exported from.
+    fn check_bounds(
+        &self,
+        pos: &[Float],
+        v_color: &[Float],
+        texcoord: &[Float],
+        normal: &[Float],
+    ) -> Result<(), LoadErrorKind> {
+        for vert in self.vertices() {
+            if vert.v.saturating_mul(3).saturating_add(2) >= pos.len() {
+                return Err(LoadErrorKind::FaceVertexOutOfBounds);
+            }
+            if !v_color.is_empty() && vert.v * 3 + 2 >= v_color.len() {
+                return Err(LoadErrorKind::FaceColorOutOfBounds);
+            }
+            if !texcoord.is_empty()
+                && vert.vt != MISSING_INDEX
+                && vert.vt.saturating_mul(2).saturating_add(1) >= texcoord.len()
+            {
+                return Err(LoadErrorKind::FaceTexCoordOutOfBounds);
+            }
+            if !normal.is_empty()
+                && vert.vn != MISSING_INDEX
+                && vert.vn.saturating_mul(3).saturating_add(2) >= normal.len()
+            {
+                return Err(LoadErrorKind::FaceNormalOutOfBounds);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[inline]
+fn add(a: [Float; 3], b: [Float; 3]) -> [Float; 3] {
+    [a[0] + b[0], a[1] + b[1], a[2] + b[2]]
+}
+
+#[inline]
+fn sub(a: [Float; 3], b: [Float; 3]) -> [Float; 3] {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+#[inline]
+fn scale(a: [Float; 3], s: Float) -> [Float; 3] {
+    [a[0] * s, a[1] * s, a[2] * s]
+}
+
+#[inline]
+fn dot(a: [Float; 3], b: [Float; 3]) -> Float {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+#[inline]
+fn cross(a: [Float; 3], b: [Float; 3]) -> [Float; 3] {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+/// Normalize a vector, zero length vectors are returned as they are.
+#[inline]
+fn normalize(a: [Float; 3]) -> [Float; 3] {
+    let length = dot(a, a).sqrt();
+    if length > 0.0 {
+        scale(a, 1.0 / length)
+    } else {
+        a
+    }
+}
+
+/// Angle between two vectors in radians, `0` if either has zero length.
+#[inline]
+fn angle_between(a: [Float; 3], b: [Float; 3]) -> Float {
+    let length = (dot(a, a) * dot(b, b)).sqrt();
+    if length > 0.0 {
+        (dot(a, b) / length).clamp(-1.0, 1.0).acos()
+    } else {
+        0.0
+    }
+}
+
+/// Normal of a polygon using Newell's method. Also works for non-planar
+/// polygons. The length of the normal is twice the area of the polygon.
+fn polygon_normal(points: impl Iterator<Item = [Float; 3]> + Clone) -> [Float; 3] {
+    points
+        .clone()
+        .zip(points.cycle().skip(1))
+        .fold([0.0; 3], |normal, (a, b)| add(normal, cross(a, b)))
+}
+
+/// Parse a number from the bytes of a word, returns `None` if it isn't one.
+///
+/// Short decimal numbers, which most files consist of, are computed directly.
+/// This is exact as long as both the digits and the power of ten are exactly
+/// representable (Clinger's fast path), other numbers are left to the standard
+/// library.
+fn parse_float_bytes(word: &[u8]) -> Option<Float> {
+    const POWERS_OF_TEN: [Float; 23] = [
+        1e0, 1e1, 1e2, 1e3, 1e4, 1e5, 1e6, 1e7, 1e8, 1e9, 1e10, 1e11, 1e12, 1e13, 1e14, 1e15, 1e16,
+        1e17, 1e18, 1e19, 1e20, 1e21, 1e22,
+    ];
+    #[cfg(feature = "use_f64")]
+    const MAX_EXPONENT: usize = 22;
+    #[cfg(not(feature = "use_f64"))]
+    const MAX_EXPONENT: usize = 10;
+
+    let parse = || std::str::from_utf8(word).ok()?.parse().ok();
+    let (negative, digits) = match word {
+        [b'-', digits @ ..] => (true, digits),
+        [b'+', digits @ ..] => (false, digits),
+        _ => (false, word),
+    };
+    let mut mantissa = 0u64;
+    let mut digit_count = 0;
+    let mut fraction_digits = None;
+    for &byte in digits {
+        match byte {
+            // More digits could overflow the mantissa
+            b'0'..=b'9' if digit_count < 19 => {
+                mantissa = mantissa * 10 + u64::from(byte - b'0');
+                digit_count += 1;
+                if let Some(count) = &mut fraction_digits {
+                    *count += 1;
+                }
+            }
+            b'.' if fraction_digits.is_none() => fraction_digits = Some(0),
+            _ => return parse(),
+        }
+    }
+    let exponent = fraction_digits.unwrap_or(0);
+    if digit_count == 0 || mantissa > 1u64 << Float::MANTISSA_DIGITS || exponent > MAX_EXPONENT {
+        return parse();
+    }
+    let value = mantissa as Float / POWERS_OF_TEN[exponent];
+    Some(if negative { -value } else { value })
+}
+
+/// Parse an index from the bytes of a word like [`isize::from_str()`] does,
+/// returns `None` if it isn't one or doesn't fit.
+fn parse_index_bytes(word: &[u8]) -> Option<isize> {
+    let (negative, digits) = match word {
+        [b'-', digits @ ..] => (true, digits),
+        [b'+', digits @ ..] => (false, digits),
+        _ => (false, word),
+    };
+    if digits.is_empty() {
+        return None;
+    }
+    digits.iter().try_fold(0isize, |index, &byte| {
+        let digit = match byte {
+            b'0'..=b'9' => isize::from(byte - b'0'),
+            _ => return None,
+        };
+        let index = index.checked_mul(10)?;
+        // Negative indices are accumulated as such to reach `isize::MIN`
+        if negative {
+            index.checked_sub(digit)
+        } else {
+            index.checked_add(digit)
+        }
+    })
+}
+
+/// Push the `value` of the last of `count` elements to the per element
+/// `values`, which are either empty or have a value for every element.
+/// Elements without a value get `default`.
+fn push_aligned(values: &mut Vec<Float>, value: Option<&[Float]>, count: usize, default: Float) {
+    match value {
+        Some(value) => {
+            values.resize((count - 1) * value.len(), default);
+            values.extend_from_slice(value);
+        }
+        None if !values.is_empty() => {
+            let n = values.len() / (count - 1);
+            values.resize(count * n, default);
+        }
+        None => {}
+    }
 }
 
 /// Parse the a string into a float3 array, returns an error if parsing failed
@@ -774,9 +2948,9 @@ fn parse_float3(val_str: SplitWhitespace) -> Result<[Float; 3], LoadError> {
         .take(3)
         .map(FromStr::from_str)
         .collect::<Result<Vec<_>, _>>()
-        .map_err(|_| LoadError::MaterialParseError)?
+        .map_err(|_| LoadErrorKind::MaterialParseError)?
         .try_into()
-        .unwrap();
+        .map_err(|_| LoadErrorKind::MaterialParseError)?;
     Ok(arr)
 }
 
@@ -784,8 +2958,8 @@ fn parse_float3(val_str: SplitWhitespace) -> Result<[Float; 3], LoadError> {
 fn parse_float(val_str: Option<&str>) -> Result<Float, LoadError> {
     val_str
         .map(FromStr::from_str)
-        .map_or(Err(LoadError::MaterialParseError), |v| {
-            v.map_err(|_| LoadError::MaterialParseError)
+        .map_or(Err(LoadErrorKind::MaterialParseError.into()), |v| {
+            v.map_err(|_| LoadErrorKind::MaterialParseError.into())
         })
 }
 
@@ -794,30 +2968,125 @@ fn parse_float(val_str: Option<&str>) -> Result<Float, LoadError> {
 /// Also handles relative face indices (negative values) which is why passing
 /// the number of positions, texcoords and normals is required.
 ///
-/// Returns `false` if an error occurred parsing the face.
-fn parse_face(
-    face_str: SplitWhitespace,
+/// Returns an error naming the offending vertex if parsing the face failed.
+fn parse_face<'a>(
+    face_str: impl Iterator<Item = &'a [u8]>,
     faces: &mut Vec<Face>,
     pos_sz: usize,
     tex_sz: usize,
     norm_sz: usize,
-) -> bool {
+) -> Result<(), LoadError> {
     let mut indices = Vec::new();
     for f in face_str {
         match VertexIndices::parse(f, pos_sz, tex_sz, norm_sz) {
             Some(v) => indices.push(v),
-            None => return false,
+            None => return Err(face_parse_error(f)),
         }
     }
-    // Check what kind face we read and push it on
-    match indices.len() {
-        1 => faces.push(Face::Point(indices[0])),
-        2 => faces.push(Face::Line(indices[0], indices[1])),
-        3 => faces.push(Face::Triangle(indices[0], indices[1], indices[2])),
-        4 => faces.push(Face::Quad(indices[0], indices[1], indices[2], indices[3])),
-        _ => faces.push(Face::Polygon(indices)),
+    // Check what kind face we read and push it on
+    match indices.len() {
+        1 => faces.push(Face::Point(indices[0])),
+        2 => faces.push(Face::Line(indices[0], indices[1])),
+        3 => faces.push(Face::Triangle(indices[0], indices[1], indices[2])),
+        4 => faces.push(Face::Quad(indices[0], indices[1], indices[2], indices[3])),
+        _ => faces.push(Face::Polygon(indices)),
+    }
+    Ok(())
+}
+
+/// A [`FaceParseError`](LoadErrorKind::FaceParseError) naming the vertex
+/// that couldn't be parsed.
+fn face_parse_error(vertex: &[u8]) -> LoadError {
+    LoadError::new(LoadErrorKind::FaceParseError).with_token(&String::from_utf8_lossy(vertex))
+}
+
+/// Triangulate a polygon by ear clipping on its best-fit plane.
+///
+/// Returns the triangles as indices into `polygon`. Polygons that are
+/// degenerate (e.g. all their vertices are collinear) are returned as a
+/// triangle fan.
+fn ear_clip(pos: &[Float], polygon: &[VertexIndices]) -> Result<Vec<[usize; 3]>, LoadError> {
+    if polygon.len() < 3 {
+        return Err(LoadErrorKind::InvalidPolygon.into());
+    }
+    let points = polygon
+        .iter()
+        .map(|vert| {
+            let v = vert.v;
+            if v.saturating_mul(3).saturating_add(2) >= pos.len() {
+                return Err(LoadErrorKind::FaceVertexOutOfBounds);
+            }
+            Ok([pos[v * 3], pos[v * 3 + 1], pos[v * 3 + 2]])
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let normal = normalize(polygon_normal(points.iter().copied()));
+    if normal == [0.0; 3] {
+        return Ok((1..polygon.len() - 1).map(|i| [0, i, i + 1]).collect());
+    }
+
+    // Project the polygon onto its plane such that it winds counter-clockwise.
+    let axis = if normal[0].abs() < 0.9 {
+        [1.0, 0.0, 0.0]
+    } else {
+        [0.0, 1.0, 0.0]
+    };
+    let u = normalize(cross(axis, normal));
+    let v = cross(normal, u);
+    let points = points
+        .iter()
+        .map(|&p| [dot(p, u), dot(p, v)])
+        .collect::<Vec<_>>();
+
+    // Twice the signed area of the triangle `a`, `b`, `c`, positive if it winds
+    // counter-clockwise.
+    let area = |a: usize, b: usize, c: usize| {
+        let (a, b, c) = (points[a], points[b], points[c]);
+        (b[0] - a[0]) * (c[1] - a[1]) - (b[1] - a[1]) * (c[0] - a[0])
+    };
+
+    let mut remaining = (0..polygon.len()).collect::<Vec<_>>();
+    let mut triangles = Vec::with_capacity(polygon.len() - 2);
+    while remaining.len() > 3 {
+        let n = remaining.len();
+        let corner = |i: usize| {
+            (
+                remaining[(i + n - 1) % n],
+                remaining[i],
+                remaining[(i + 1) % n],
+            )
+        };
+        // An ear is a convex corner whose triangle contains no other vertex.
+        let ear = (0..n).find(|&i| {
+            let (a, b, c) = corner(i);
+            area(a, b, c) > 0.0
+                && remaining.iter().all(|&p| {
+                    p == a
+                        || p == b
+                        || p == c
+                        || area(a, b, p) <= 0.0
+                        || area(b, c, p) <= 0.0
+                        || area(c, a, p) <= 0.0
+                })
+        });
+        // Self-intersecting polygons may have no ears left, clip their most
+        // convex corner instead.
+        let ear = ear.unwrap_or_else(|| {
+            (0..n)
+                .max_by(|&i, &j| {
+                    let (a, b, c) = corner(i);
+                    let (d, e, f) = corner(j);
+                    area(a, b, c).total_cmp(&area(d, e, f))
+                })
+                .unwrap_or_default()
+        });
+        let (a, b, c) = corner(ear);
+        triangles.push([a, b, c]);
+        remaining.remove(ear);
     }
-    true
+    triangles.push([remaining[0], remaining[1], remaining[2]]);
+
+    Ok(triangles)
 }
 
 /// Add a vertex to a mesh by either re-using an existing index (e.g. it's in
@@ -837,7 +3106,7 @@ fn add_vertex(
         None => {
             let v = vert.v;
             if v.saturating_mul(3).saturating_add(2) >= pos.len() {
-                return Err(LoadError::FaceVertexOutOfBounds);
+                return Err(LoadErrorKind::FaceVertexOutOfBounds.into());
             }
             // Add the vertex to the mesh
             mesh.positions.push(pos[v * 3]);
@@ -846,7 +3115,7 @@ fn add_vertex(
             if !texcoord.is_empty() && vert.vt != MISSING_INDEX {
                 let vt = vert.vt;
                 if vt * 2 + 1 >= texcoord.len() {
-                    return Err(LoadError::FaceTexCoordOutOfBounds);
+                    return Err(LoadErrorKind::FaceTexCoordOutOfBounds.into());
                 }
                 mesh.texcoords.push(texcoord[vt * 2]);
                 mesh.texcoords.push(texcoord[vt * 2 + 1]);
@@ -854,7 +3123,7 @@ fn add_vertex(
             if !normal.is_empty() && vert.vn != MISSING_INDEX {
                 let vn = vert.vn;
                 if vn * 3 + 2 >= normal.len() {
-                    return Err(LoadError::FaceNormalOutOfBounds);
+                    return Err(LoadErrorKind::FaceNormalOutOfBounds.into());
                 }
                 mesh.normals.push(normal[vn * 3]);
                 mesh.normals.push(normal[vn * 3 + 1]);
@@ -862,7 +3131,7 @@ fn add_vertex(
             }
             if !v_color.is_empty() {
                 if v * 3 + 2 >= v_color.len() {
-                    return Err(LoadError::FaceColorOutOfBounds);
+                    return Err(LoadErrorKind::FaceColorOutOfBounds.into());
                 }
                 mesh.vertex_color.push(v_color[v * 3]);
                 mesh.vertex_color.push(v_color[v * 3 + 1]);
@@ -876,16 +3145,39 @@ fn add_vertex(
     Ok(())
 }
 
+/// Number of faces exported to a mesh so far.
+///
+/// While exporting, `face_arities` holds an entry for every face, including
+/// triangles, unless all faces are being triangulated.
+#[inline]
+fn exported_face_count(mesh: &Mesh, load_options: &LoadOptions) -> usize {
+    if load_options.triangulate {
+        mesh.indices.len() / 3
+    } else {
+        mesh.face_arities.len()
+    }
+}
+
 /// Export a list of faces to a mesh and return it, optionally converting quads
 /// to tris.
+#[allow(clippy::too_many_arguments)]
 fn export_faces(
     pos: &[Float],
+    pos_w: &[Float],
     v_color: &[Float],
+    v_alpha: &[Float],
     texcoord: &[Float],
+    texcoord_w: &[Float],
     normal: &[Float],
     faces: &[Face],
+    face_attributes: &[FaceAttributes],
+    points: &[Face],
+    point_attributes: &[FaceAttributes],
+    polylines: &[Face],
+    polyline_attributes: &[FaceAttributes],
     mat_id: Option<usize>,
     load_options: &LoadOptions,
+    warnings: &mut Vec<LoadWarning>,
 ) -> Result<Mesh, LoadError> {
     let mut index_map = HashMap::new();
     let mut mesh = Mesh {
@@ -893,77 +3185,153 @@ fn export_faces(
         ..Default::default()
     };
     let mut is_all_triangles = true;
+    let mut has_smoothing_groups = false;
+    let ear_clipping =
+        load_options.triangulate && load_options.triangulation == Triangulation::EarClipping;
+
+    for (face, (f, attributes)) in faces.iter().zip(face_attributes).enumerate() {
+        if load_options.skip_invalid_lines {
+            if let Err(kind) = f.check_bounds(pos, v_color, texcoord, normal) {
+                let e = LoadError::new(kind).at_line(attributes.line, Some("f"));
+                warnings.push(LoadWarning::SkippedLine(e));
+                continue;
+            }
+        }
 
-    for f in faces {
+        // Errors are reported at the line the face was read from
+        let at_line = |e: LoadError| e.at_line(attributes.line, None);
         // Optimized paths for Triangles and Quads, Polygon handles the general case of
         // an unknown length triangle fan.
         match *f {
-            Face::Point(ref a) => {
-                if !load_options.ignore_points {
-                    add_vertex(&mut mesh, &mut index_map, a, pos, v_color, texcoord, normal)?;
-                    if load_options.triangulate {
-                        add_vertex(&mut mesh, &mut index_map, a, pos, v_color, texcoord, normal)?;
-                        add_vertex(&mut mesh, &mut index_map, a, pos, v_color, texcoord, normal)?;
-                    } else {
-                        is_all_triangles = false;
-                        mesh.face_arities.push(1);
-                    }
-                }
-            }
+            // Points are exported separately, see `check_primitive`
+            Face::Point(_) => {}
             Face::Line(ref a, ref b) => {
-                if !load_options.ignore_lines {
-                    add_vertex(&mut mesh, &mut index_map, a, pos, v_color, texcoord, normal)?;
-                    add_vertex(&mut mesh, &mut index_map, b, pos, v_color, texcoord, normal)?;
-                    if load_options.triangulate {
-                        add_vertex(&mut mesh, &mut index_map, b, pos, v_color, texcoord, normal)?;
-                    } else {
-                        is_all_triangles = false;
-                        mesh.face_arities.push(2);
-                    }
+                add_vertex(&mut mesh, &mut index_map, a, pos, v_color, texcoord, normal)
+                    .map_err(at_line)?;
+                add_vertex(&mut mesh, &mut index_map, b, pos, v_color, texcoord, normal)
+                    .map_err(at_line)?;
+                if load_options.triangulate {
+                    add_vertex(&mut mesh, &mut index_map, b, pos, v_color, texcoord, normal)
+                        .map_err(at_line)?;
+                } else {
+                    is_all_triangles = false;
+                    mesh.face_arities.push(2);
                 }
             }
             Face::Triangle(ref a, ref b, ref c) => {
-                add_vertex(&mut mesh, &mut index_map, a, pos, v_color, texcoord, normal)?;
-                add_vertex(&mut mesh, &mut index_map, b, pos, v_color, texcoord, normal)?;
-                add_vertex(&mut mesh, &mut index_map, c, pos, v_color, texcoord, normal)?;
+                add_vertex(&mut mesh, &mut index_map, a, pos, v_color, texcoord, normal)
+                    .map_err(at_line)?;
+                add_vertex(&mut mesh, &mut index_map, b, pos, v_color, texcoord, normal)
+                    .map_err(at_line)?;
+                add_vertex(&mut mesh, &mut index_map, c, pos, v_color, texcoord, normal)
+                    .map_err(at_line)?;
                 if !load_options.triangulate {
                     mesh.face_arities.push(3);
                 }
             }
+            Face::Quad(a, b, c, d) if ear_clipping => {
+                let quad = [a, b, c, d];
+                for i in ear_clip(pos, &quad).map_err(at_line)?.into_iter().flatten() {
+                    add_vertex(
+                        &mut mesh,
+                        &mut index_map,
+                        &quad[i],
+                        pos,
+                        v_color,
+                        texcoord,
+                        normal,
+                    )
+                    .map_err(at_line)?;
+                }
+            }
             Face::Quad(ref a, ref b, ref c, ref d) => {
-                add_vertex(&mut mesh, &mut index_map, a, pos, v_color, texcoord, normal)?;
-                add_vertex(&mut mesh, &mut index_map, b, pos, v_color, texcoord, normal)?;
-                add_vertex(&mut mesh, &mut index_map, c, pos, v_color, texcoord, normal)?;
+                add_vertex(&mut mesh, &mut index_map, a, pos, v_color, texcoord, normal)
+                    .map_err(at_line)?;
+                add_vertex(&mut mesh, &mut index_map, b, pos, v_color, texcoord, normal)
+                    .map_err(at_line)?;
+                add_vertex(&mut mesh, &mut index_map, c, pos, v_color, texcoord, normal)
+                    .map_err(at_line)?;
 
                 if load_options.triangulate {
-                    add_vertex(&mut mesh, &mut index_map, a, pos, v_color, texcoord, normal)?;
-                    add_vertex(&mut mesh, &mut index_map, c, pos, v_color, texcoord, normal)?;
-                    add_vertex(&mut mesh, &mut index_map, d, pos, v_color, texcoord, normal)?;
+                    add_vertex(&mut mesh, &mut index_map, a, pos, v_color, texcoord, normal)
+                        .map_err(at_line)?;
+                    add_vertex(&mut mesh, &mut index_map, c, pos, v_color, texcoord, normal)
+                        .map_err(at_line)?;
+                    add_vertex(&mut mesh, &mut index_map, d, pos, v_color, texcoord, normal)
+                        .map_err(at_line)?;
                 } else {
-                    add_vertex(&mut mesh, &mut index_map, d, pos, v_color, texcoord, normal)?;
+                    add_vertex(&mut mesh, &mut index_map, d, pos, v_color, texcoord, normal)
+                        .map_err(at_line)?;
                     is_all_triangles = false;
                     mesh.face_arities.push(4);
                 }
             }
+            Face::Polygon(ref indices) if ear_clipping => {
+                for i in ear_clip(pos, indices)
+                    .map_err(at_line)?
+                    .into_iter()
+                    .flatten()
+                {
+                    add_vertex(
+                        &mut mesh,
+                        &mut index_map,
+                        &indices[i],
+                        pos,
+                        v_color,
+                        texcoord,
+                        normal,
+                    )
+                    .map_err(at_line)?;
+                }
+            }
             Face::Polygon(ref indices) => {
                 if load_options.triangulate {
-                    let a = indices.first().ok_or(LoadError::InvalidPolygon)?;
-                    let mut b = indices.get(1).ok_or(LoadError::InvalidPolygon)?;
+                    let a = indices
+                        .first()
+                        .ok_or(LoadErrorKind::InvalidPolygon.into())
+                        .map_err(at_line)?;
+                    let mut b = indices
+                        .get(1)
+                        .ok_or(LoadErrorKind::InvalidPolygon.into())
+                        .map_err(at_line)?;
                     for c in indices.iter().skip(2) {
-                        add_vertex(&mut mesh, &mut index_map, a, pos, v_color, texcoord, normal)?;
-                        add_vertex(&mut mesh, &mut index_map, b, pos, v_color, texcoord, normal)?;
-                        add_vertex(&mut mesh, &mut index_map, c, pos, v_color, texcoord, normal)?;
+                        add_vertex(&mut mesh, &mut index_map, a, pos, v_color, texcoord, normal)
+                            .map_err(at_line)?;
+                        add_vertex(&mut mesh, &mut index_map, b, pos, v_color, texcoord, normal)
+                            .map_err(at_line)?;
+                        add_vertex(&mut mesh, &mut index_map, c, pos, v_color, texcoord, normal)
+                            .map_err(at_line)?;
                         b = c;
                     }
                 } else {
                     for i in indices.iter() {
-                        add_vertex(&mut mesh, &mut index_map, i, pos, v_color, texcoord, normal)?;
+                        add_vertex(&mut mesh, &mut index_map, i, pos, v_color, texcoord, normal)
+                            .map_err(at_line)?;
                     }
                     is_all_triangles = false;
                     mesh.face_arities.push(indices.len() as u32);
                 }
             }
         }
+        if f.is_degenerate(pos) {
+            warnings.push(LoadWarning::DegenerateFace {
+                line: attributes.line,
+            });
+        }
+
+        // Every face exported for this one shares its smoothing group,
+        // material and source face.
+        let face_count = exported_face_count(&mesh, load_options);
+        mesh.smoothing_groups
+            .resize(face_count, attributes.smoothing_group.unwrap_or(0));
+        has_smoothing_groups |= attributes.smoothing_group.is_some();
+        if load_options.per_face_materials {
+            mesh.face_material_ids
+                .resize(face_count, attributes.material_id);
+        }
+        if load_options.source_faces {
+            mesh.source_faces.resize(face_count, face as u32);
+        }
     }
 
     if is_all_triangles {
@@ -971,9 +3339,159 @@ fn export_faces(
         mesh.face_arities = Vec::new();
     }
 
+    if !has_smoothing_groups {
+        mesh.smoothing_groups = Vec::new();
+    }
+
+    for (point, attributes) in points.iter().zip(point_attributes) {
+        if !check_primitive(
+            point,
+            attributes,
+            pos,
+            v_color,
+            texcoord,
+            normal,
+            load_options,
+            warnings,
+        )? {
+            continue;
+        }
+        for vert in &point.vertices() {
+            // Points share the vertices of the faces, but not their indices
+            add_vertex(
+                &mut mesh,
+                &mut index_map,
+                vert,
+                pos,
+                v_color,
+                texcoord,
+                normal,
+            )?;
+            mesh.point_indices.extend(mesh.indices.pop());
+        }
+    }
+
+    for (polyline, attributes) in polylines.iter().zip(polyline_attributes) {
+        if !check_primitive(
+            polyline,
+            attributes,
+            pos,
+            v_color,
+            texcoord,
+            normal,
+            load_options,
+            warnings,
+        )? {
+            continue;
+        }
+        let vertices = polyline.vertices();
+        for vert in &vertices {
+            // Polylines share the vertices of the faces, but not their indices
+            add_vertex(
+                &mut mesh,
+                &mut index_map,
+                vert,
+                pos,
+                v_color,
+                texcoord,
+                normal,
+            )?;
+            mesh.line_indices.extend(mesh.indices.pop());
+        }
+        mesh.line_arities.push(vertices.len() as u32);
+    }
+
+    let preserve_w = load_options.preserve_w && !(pos_w.is_empty() && texcoord_w.is_empty());
+    if load_options.source_vertices || preserve_w || !v_alpha.is_empty() {
+        let mut source_vertices = vec![
+            VertexIndices {
+                v: MISSING_INDEX,
+                vt: MISSING_INDEX,
+                vn: MISSING_INDEX,
+            };
+            index_map.len()
+        ];
+        for (&vert, &index) in &index_map {
+            source_vertices[index as usize] = vert;
+        }
+        if preserve_w {
+            if !pos_w.is_empty() {
+                mesh.position_w = source_vertices.iter().map(|vert| pos_w[vert.v]).collect();
+            }
+            // Like the texture coordinates, only for vertices that have one
+            if !texcoord_w.is_empty() {
+                mesh.texcoord_w = source_vertices
+                    .iter()
+                    .filter(|vert| vert.vt != MISSING_INDEX)
+                    .map(|vert| texcoord_w[vert.vt])
+                    .collect();
+            }
+        }
+        if !v_alpha.is_empty() {
+            mesh.vertex_color_alpha = source_vertices.iter().map(|vert| v_alpha[vert.v]).collect();
+        }
+        if load_options.source_vertices {
+            mesh.source_vertices = source_vertices;
+        }
+    }
+
+    if mesh.normals.is_empty() {
+        mesh.generate_normals(load_options.generate_normals, true);
+    }
+
+    #[cfg(feature = "tangents")]
+    if load_options.generate_tangents {
+        mesh.generate_tangents(true);
+    }
+
     Ok(mesh)
 }
 
+/// Check whether a point or polyline is to be exported, i.e. it is not ignored
+/// and its vertices are within bounds.
+#[allow(clippy::too_many_arguments)]
+fn check_primitive(
+    primitive: &Face,
+    attributes: &FaceAttributes,
+    pos: &[Float],
+    v_color: &[Float],
+    texcoord: &[Float],
+    normal: &[Float],
+    load_options: &LoadOptions,
+    warnings: &mut Vec<LoadWarning>,
+) -> Result<bool, LoadError> {
+    let (ignored, warning, directive) = match primitive {
+        Face::Point(_) => (
+            load_options.ignore_points,
+            LoadWarning::DiscardedPoint {
+                line: attributes.line,
+            },
+            // Points may come from `p` or `f` statements
+            None,
+        ),
+        _ => (
+            load_options.ignore_lines,
+            LoadWarning::DiscardedLine {
+                line: attributes.line,
+            },
+            Some("l"),
+        ),
+    };
+    if ignored {
+        warnings.push(warning);
+        return Ok(false);
+    }
+    if let Err(kind) = primitive.check_bounds(pos, v_color, texcoord, normal) {
+        let e = LoadError::new(kind).at_line(attributes.line, directive);
+        if !load_options.skip_invalid_lines {
+            return Err(e);
+        }
+        warnings.push(LoadWarning::SkippedLine(e));
+        return Ok(false);
+    }
+    Ok(true)
+}
+
 /// Add a vertex to a mesh by either re-using an existing index (e.g. it's in
 /// the `index_map`) or appending the position, texcoord and normal as
 /// appropriate and creating a new vertex.
@@ -996,7 +3514,7 @@ fn add_vertex_multi_index(
             let vertex = vert.v;
 
             if vertex.saturating_mul(3).saturating_add(2) >= pos.len() {
-                return Err(LoadError::FaceVertexOutOfBounds);
+                return Err(LoadErrorKind::FaceVertexOutOfBounds.into());
             }
 
             // Add the vertex to the mesh.
@@ -1013,7 +3531,7 @@ fn add_vertex_multi_index(
                 let vertex = vert.v;
 
                 if vertex * 3 + 2 >= v_color.len() {
-                    return Err(LoadError::FaceColorOutOfBounds);
+                    return Err(LoadErrorKind::FaceColorOutOfBounds.into());
                 }
 
                 mesh.vertex_color.push(v_color[vertex * 3]);
@@ -1049,7 +3567,7 @@ fn add_vertex_multi_index(
                     let vt = vert.vt;
 
                     if vt * 2 + 1 >= texcoord.len() {
-                        return Err(LoadError::FaceTexCoordOutOfBounds);
+                        return Err(LoadErrorKind::FaceTexCoordOutOfBounds.into());
                     }
 
                     mesh.texcoords.push(texcoord[vt * 2]);
@@ -1090,7 +3608,7 @@ fn add_vertex_multi_index(
                     let vn = vert.vn;
 
                     if vn * 3 + 2 >= normal.len() {
-                        return Err(LoadError::FaceNormalOutOfBounds);
+                        return Err(LoadErrorKind::FaceNormalOutOfBounds.into());
                     }
 
                     mesh.normals.push(normal[vn * 3]);
@@ -1110,14 +3628,24 @@ fn add_vertex_multi_index(
 
 /// Export a list of faces to a mesh and return it, optionally converting quads
 /// to tris.
+#[allow(clippy::too_many_arguments)]
 fn export_faces_multi_index(
     pos: &[Float],
+    pos_w: &[Float],
     v_color: &[Float],
+    v_alpha: &[Float],
     texcoord: &[Float],
+    texcoord_w: &[Float],
     normal: &[Float],
     faces: &[Face],
+    face_attributes: &[FaceAttributes],
+    points: &[Face],
+    point_attributes: &[FaceAttributes],
+    polylines: &[Face],
+    polyline_attributes: &[FaceAttributes],
     mat_id: Option<usize>,
     load_options: &LoadOptions,
+    warnings: &mut Vec<LoadWarning>,
 ) -> Result<Mesh, LoadError> {
     let mut index_map = HashMap::new();
     let mut normal_index_map = HashMap::new();
@@ -1129,66 +3657,52 @@ fn export_faces_multi_index(
     };
 
     let mut is_all_triangles = true;
+    let mut has_smoothing_groups = false;
+    let ear_clipping =
+        load_options.triangulate && load_options.triangulation == Triangulation::EarClipping;
+
+    for (face, (f, attributes)) in faces.iter().zip(face_attributes).enumerate() {
+        if load_options.skip_invalid_lines {
+            if let Err(kind) = f.check_bounds(pos, v_color, texcoord, normal) {
+                let e = LoadError::new(kind).at_line(attributes.line, Some("f"));
+                warnings.push(LoadWarning::SkippedLine(e));
+                continue;
+            }
+        }
 
-    for f in faces {
+        // Errors are reported at the line the face was read from
+        let at_line = |e: LoadError| e.at_line(attributes.line, None);
         // Optimized paths for Triangles and Quads, Polygon handles the general case of
         // an unknown length triangle fan
         match *f {
-            Face::Point(ref a) => {
-                if !load_options.ignore_points {
-                    add_vertex_multi_index(
-                        &mut mesh,
-                        &mut index_map,
-                        &mut normal_index_map,
-                        &mut texcoord_index_map,
-                        a,
-                        pos,
-                        v_color,
-                        texcoord,
-                        normal,
-                    )?;
-                    if load_options.triangulate {
-                        add_vertex_multi_index(
-                            &mut mesh,
-                            &mut index_map,
-                            &mut normal_index_map,
-                            &mut texcoord_index_map,
-                            a,
-                            pos,
-                            v_color,
-                            texcoord,
-                            normal,
-                        )?;
-                        add_vertex_multi_index(
-                            &mut mesh,
-                            &mut index_map,
-                            &mut normal_index_map,
-                            &mut texcoord_index_map,
-                            a,
-                            pos,
-                            v_color,
-                            texcoord,
-                            normal,
-                        )?;
-                    } else {
-                        is_all_triangles = false;
-                        mesh.face_arities.push(1);
-                    }
-                }
-            }
+            // Points are exported separately, see `check_primitive`
+            Face::Point(_) => {}
             Face::Line(ref a, ref b) => {
-                if !load_options.ignore_lines {
-                    add_vertex_multi_index(
-                        &mut mesh,
-                        &mut index_map,
-                        &mut normal_index_map,
-                        &mut texcoord_index_map,
-                        a,
-                        pos,
-                        v_color,
-                        texcoord,
-                        normal,
-                    )?;
+                add_vertex_multi_index(
+                    &mut mesh,
+                    &mut index_map,
+                    &mut normal_index_map,
+                    &mut texcoord_index_map,
+                    a,
+                    pos,
+                    v_color,
+                    texcoord,
+                    normal,
+                )
+                .map_err(at_line)?;
+                add_vertex_multi_index(
+                    &mut mesh,
+                    &mut index_map,
+                    &mut normal_index_map,
+                    &mut texcoord_index_map,
+                    b,
+                    pos,
+                    v_color,
+                    texcoord,
+                    normal,
+                )
+                .map_err(at_line)?;
+                if load_options.triangulate {
                     add_vertex_multi_index(
                         &mut mesh,
                         &mut index_map,
@@ -1199,23 +3713,11 @@ fn export_faces_multi_index(
                         v_color,
                         texcoord,
                         normal,
-                    )?;
-                    if load_options.triangulate {
-                        add_vertex_multi_index(
-                            &mut mesh,
-                            &mut index_map,
-                            &mut normal_index_map,
-                            &mut texcoord_index_map,
-                            b,
-                            pos,
-                            v_color,
-                            texcoord,
-                            normal,
-                        )?;
-                    } else {
-                        is_all_triangles = false;
-                        mesh.face_arities.push(2);
-                    }
+                    )
+                    .map_err(at_line)?;
+                } else {
+                    is_all_triangles = false;
+                    mesh.face_arities.push(2);
                 }
             }
             Face::Triangle(ref a, ref b, ref c) => {
@@ -1229,7 +3731,8 @@ fn export_faces_multi_index(
                     v_color,
                     texcoord,
                     normal,
-                )?;
+                )
+                .map_err(at_line)?;
                 add_vertex_multi_index(
                     &mut mesh,
                     &mut index_map,
@@ -1240,7 +3743,8 @@ fn export_faces_multi_index(
                     v_color,
                     texcoord,
                     normal,
-                )?;
+                )
+                .map_err(at_line)?;
                 add_vertex_multi_index(
                     &mut mesh,
                     &mut index_map,
@@ -1251,11 +3755,29 @@ fn export_faces_multi_index(
                     v_color,
                     texcoord,
                     normal,
-                )?;
+                )
+                .map_err(at_line)?;
                 if !load_options.triangulate {
                     mesh.face_arities.push(3);
                 }
             }
+            Face::Quad(a, b, c, d) if ear_clipping => {
+                let quad = [a, b, c, d];
+                for i in ear_clip(pos, &quad).map_err(at_line)?.into_iter().flatten() {
+                    add_vertex_multi_index(
+                        &mut mesh,
+                        &mut index_map,
+                        &mut normal_index_map,
+                        &mut texcoord_index_map,
+                        &quad[i],
+                        pos,
+                        v_color,
+                        texcoord,
+                        normal,
+                    )
+                    .map_err(at_line)?;
+                }
+            }
             Face::Quad(ref a, ref b, ref c, ref d) => {
                 add_vertex_multi_index(
                     &mut mesh,
@@ -1267,7 +3789,8 @@ fn export_faces_multi_index(
                     v_color,
                     texcoord,
                     normal,
-                )?;
+                )
+                .map_err(at_line)?;
                 add_vertex_multi_index(
                     &mut mesh,
                     &mut index_map,
@@ -1278,7 +3801,8 @@ fn export_faces_multi_index(
                     v_color,
                     texcoord,
                     normal,
-                )?;
+                )
+                .map_err(at_line)?;
                 add_vertex_multi_index(
                     &mut mesh,
                     &mut index_map,
@@ -1289,7 +3813,8 @@ fn export_faces_multi_index(
                     v_color,
                     texcoord,
                     normal,
-                )?;
+                )
+                .map_err(at_line)?;
 
                 if load_options.triangulate {
                     add_vertex_multi_index(
@@ -1302,7 +3827,8 @@ fn export_faces_multi_index(
                         v_color,
                         texcoord,
                         normal,
-                    )?;
+                    )
+                    .map_err(at_line)?;
                     add_vertex_multi_index(
                         &mut mesh,
                         &mut index_map,
@@ -1313,7 +3839,8 @@ fn export_faces_multi_index(
                         v_color,
                         texcoord,
                         normal,
-                    )?;
+                    )
+                    .map_err(at_line)?;
                     add_vertex_multi_index(
                         &mut mesh,
                         &mut index_map,
@@ -1324,7 +3851,8 @@ fn export_faces_multi_index(
                         v_color,
                         texcoord,
                         normal,
-                    )?;
+                    )
+                    .map_err(at_line)?;
                 } else {
                     add_vertex_multi_index(
                         &mut mesh,
@@ -1336,15 +3864,42 @@ fn export_faces_multi_index(
                         v_color,
                         texcoord,
                         normal,
-                    )?;
+                    )
+                    .map_err(at_line)?;
                     is_all_triangles = false;
                     mesh.face_arities.push(4);
                 }
             }
+            Face::Polygon(ref indices) if ear_clipping => {
+                for i in ear_clip(pos, indices)
+                    .map_err(at_line)?
+                    .into_iter()
+                    .flatten()
+                {
+                    add_vertex_multi_index(
+                        &mut mesh,
+                        &mut index_map,
+                        &mut normal_index_map,
+                        &mut texcoord_index_map,
+                        &indices[i],
+                        pos,
+                        v_color,
+                        texcoord,
+                        normal,
+                    )
+                    .map_err(at_line)?;
+                }
+            }
             Face::Polygon(ref indices) => {
                 if load_options.triangulate {
-                    let a = indices.first().ok_or(LoadError::InvalidPolygon)?;
-                    let mut b = indices.get(1).ok_or(LoadError::InvalidPolygon)?;
+                    let a = indices
+                        .first()
+                        .ok_or(LoadErrorKind::InvalidPolygon.into())
+                        .map_err(at_line)?;
+                    let mut b = indices
+                        .get(1)
+                        .ok_or(LoadErrorKind::InvalidPolygon.into())
+                        .map_err(at_line)?;
                     for c in indices.iter().skip(2) {
                         add_vertex_multi_index(
                             &mut mesh,
@@ -1356,7 +3911,8 @@ fn export_faces_multi_index(
                             v_color,
                             texcoord,
                             normal,
-                        )?;
+                        )
+                        .map_err(at_line)?;
                         add_vertex_multi_index(
                             &mut mesh,
                             &mut index_map,
@@ -1367,7 +3923,8 @@ fn export_faces_multi_index(
                             v_color,
                             texcoord,
                             normal,
-                        )?;
+                        )
+                        .map_err(at_line)?;
                         add_vertex_multi_index(
                             &mut mesh,
                             &mut index_map,
@@ -1378,7 +3935,8 @@ fn export_faces_multi_index(
                             v_color,
                             texcoord,
                             normal,
-                        )?;
+                        )
+                        .map_err(at_line)?;
                         b = c;
                     }
                 } else {
@@ -1393,13 +3951,33 @@ fn export_faces_multi_index(
                             v_color,
                             texcoord,
                             normal,
-                        )?;
+                        )
+                        .map_err(at_line)?;
                     }
                     is_all_triangles = false;
                     mesh.face_arities.push(indices.len() as u32);
                 }
             }
         }
+        if f.is_degenerate(pos) {
+            warnings.push(LoadWarning::DegenerateFace {
+                line: attributes.line,
+            });
+        }
+
+        // Every face exported for this one shares its smoothing group,
+        // material and source face.
+        let face_count = exported_face_count(&mesh, load_options);
+        mesh.smoothing_groups
+            .resize(face_count, attributes.smoothing_group.unwrap_or(0));
+        has_smoothing_groups |= attributes.smoothing_group.is_some();
+        if load_options.per_face_materials {
+            mesh.face_material_ids
+                .resize(face_count, attributes.material_id);
+        }
+        if load_options.source_faces {
+            mesh.source_faces.resize(face_count, face as u32);
+        }
     }
 
     if is_all_triangles {
@@ -1407,15 +3985,189 @@ fn export_faces_multi_index(
         mesh.face_arities = Vec::new();
     }
 
+    if !has_smoothing_groups {
+        mesh.smoothing_groups = Vec::new();
+    }
+
+    for (point, attributes) in points.iter().zip(point_attributes) {
+        if !check_primitive(
+            point,
+            attributes,
+            pos,
+            v_color,
+            texcoord,
+            normal,
+            load_options,
+            warnings,
+        )? {
+            continue;
+        }
+        for vert in &point.vertices() {
+            // Points share the positions of the faces, but not their indices
+            add_vertex_multi_index(
+                &mut mesh,
+                &mut index_map,
+                &mut normal_index_map,
+                &mut texcoord_index_map,
+                vert,
+                pos,
+                v_color,
+                &[],
+                &[],
+            )?;
+            mesh.point_indices.extend(mesh.indices.pop());
+        }
+    }
+
+    // Texture coordinates of polylines are only kept if all of their vertices
+    // have one
+    let line_texcoord = if polylines
+        .iter()
+        .flat_map(Face::vertices)
+        .all(|vert| vert.vt != MISSING_INDEX)
+    {
+        texcoord
+    } else {
+        &[]
+    };
+    for (polyline, attributes) in polylines.iter().zip(polyline_attributes) {
+        if !check_primitive(
+            polyline,
+            attributes,
+            pos,
+            v_color,
+            texcoord,
+            normal,
+            load_options,
+            warnings,
+        )? {
+            continue;
+        }
+        let vertices = polyline.vertices();
+        for vert in &vertices {
+            // Polylines share the vertices of the faces, but not their indices
+            add_vertex_multi_index(
+                &mut mesh,
+                &mut index_map,
+                &mut normal_index_map,
+                &mut texcoord_index_map,
+                vert,
+                pos,
+                v_color,
+                line_texcoord,
+                &[],
+            )?;
+            mesh.line_indices.extend(mesh.indices.pop());
+            if !line_texcoord.is_empty() {
+                mesh.line_texcoord_indices
+                    .extend(mesh.texcoord_indices.pop());
+            }
+        }
+        mesh.line_arities.push(vertices.len() as u32);
+    }
+
+    if load_options.preserve_w {
+        if !pos_w.is_empty() {
+            mesh.position_w = vec![1.0; index_map.len()];
+            for (&v, &index) in &index_map {
+                mesh.position_w[index as usize] = pos_w[v];
+            }
+        }
+        if !texcoord_w.is_empty() {
+            mesh.texcoord_w = vec![0.0; texcoord_index_map.len()];
+            for (&vt, &index) in &texcoord_index_map {
+                mesh.texcoord_w[index as usize] = texcoord_w[vt];
+            }
+        }
+    }
+
+    if !v_alpha.is_empty() {
+        mesh.vertex_color_alpha = vec![1.0; index_map.len()];
+        for (&v, &index) in &index_map {
+            mesh.vertex_color_alpha[index as usize] = v_alpha[v];
+        }
+    }
+
+    // The source of each corner is taken from the index maps before merging and
+    // reordering renumber the elements, and stored once they are done
+    let source_vertices = load_options.source_vertices.then(|| {
+        // Invert the maps from the indices of the file to those of the mesh
+        let invert = |map: &HashMap<usize, u32>| {
+            let mut inverse = vec![MISSING_INDEX; map.len()];
+            for (&i, &index) in map {
+                inverse[index as usize] = i;
+            }
+            inverse
+        };
+        let (v, vt, vn) = (
+            invert(&index_map),
+            invert(&texcoord_index_map),
+            invert(&normal_index_map),
+        );
+        let source_index = |inverse: &[usize], indices: &[u32], corner: usize| {
+            indices
+                .get(corner)
+                .map_or(MISSING_INDEX, |&index| inverse[index as usize])
+        };
+        (0..mesh.indices.len())
+            .map(|corner| VertexIndices {
+                v: v[mesh.indices[corner] as usize],
+                vt: source_index(&vt, &mesh.texcoord_indices, corner),
+                vn: source_index(&vn, &mesh.normal_indices, corner),
+            })
+            .collect::<Vec<_>>()
+    });
+
+    if mesh.normals.is_empty() {
+        mesh.generate_normals(load_options.generate_normals, false);
+    }
+
+    #[cfg(feature = "tangents")]
+    if load_options.generate_tangents {
+        mesh.generate_tangents(false);
+    }
+
     #[cfg(feature = "merging")]
     if load_options.merge_identical_points {
         if !mesh.vertex_color.is_empty() {
             mesh.vertex_color_indices = mesh.indices.clone();
-            merge_identical_points::<3>(&mut mesh.vertex_color, &mut mesh.vertex_color_indices);
+            if mesh.vertex_color_alpha.is_empty() {
+                merge_identical_points::<3>(&mut mesh.vertex_color, &mut mesh.vertex_color_indices);
+            } else {
+                let mut colors = join_w::<3>(&mesh.vertex_color, &mesh.vertex_color_alpha);
+                merge_identical_points::<4>(&mut colors, &mut mesh.vertex_color_indices);
+                (mesh.vertex_color, mesh.vertex_color_alpha) = split_w::<3>(&colors);
+            }
         }
-        merge_identical_points::<3>(&mut mesh.positions, &mut mesh.indices);
+        // Points and polylines are merged along with the faces
+        let face_index_count = mesh.indices.len();
+        let point_index_count = mesh.point_indices.len();
+        mesh.indices.append(&mut mesh.point_indices);
+        mesh.indices.append(&mut mesh.line_indices);
+        if mesh.position_w.is_empty() {
+            merge_identical_points::<3>(&mut mesh.positions, &mut mesh.indices);
+        } else {
+            // Positions are only identical if their w is as well
+            let mut positions = join_w::<3>(&mesh.positions, &mesh.position_w);
+            merge_identical_points::<4>(&mut positions, &mut mesh.indices);
+            (mesh.positions, mesh.position_w) = split_w::<3>(&positions);
+        }
+        mesh.line_indices = mesh.indices.split_off(face_index_count + point_index_count);
+        mesh.point_indices = mesh.indices.split_off(face_index_count);
         merge_identical_points::<3>(&mut mesh.normals, &mut mesh.normal_indices);
-        merge_identical_points::<2>(&mut mesh.texcoords, &mut mesh.texcoord_indices);
+        #[cfg(feature = "tangents")]
+        merge_identical_points::<4>(&mut mesh.tangents, &mut mesh.tangent_indices);
+        let face_index_count = mesh.texcoord_indices.len();
+        mesh.texcoord_indices
+            .append(&mut mesh.line_texcoord_indices);
+        if mesh.texcoord_w.is_empty() {
+            merge_identical_points::<2>(&mut mesh.texcoords, &mut mesh.texcoord_indices);
+        } else {
+            let mut texcoords = join_w::<2>(&mesh.texcoords, &mesh.texcoord_w);
+            merge_identical_points::<3>(&mut texcoords, &mut mesh.texcoord_indices);
+            (mesh.texcoords, mesh.texcoord_w) = split_w::<2>(&texcoords);
+        }
+        mesh.line_texcoord_indices = mesh.texcoord_indices.split_off(face_index_count);
     }
 
     #[cfg(feature = "reordering")]
@@ -1423,14 +4175,42 @@ fn export_faces_multi_index(
         reorder_data(&mut mesh);
     }
 
+    if let Some(source_vertices) = source_vertices {
+        mesh.source_vertices = source_vertices;
+    }
+
     Ok(mesh)
 }
 
 #[cfg(feature = "reordering")]
 #[inline]
 fn reorder_data(mesh: &mut Mesh) {
+    // Texture coordinates of polylines keep their own indices, they are
+    // appended to those of the faces
+    let line_texcoords = mesh
+        .line_texcoord_indices
+        .iter()
+        .flat_map(|&index| {
+            let index = index as usize * 2;
+            [mesh.texcoords[index], mesh.texcoords[index + 1]]
+        })
+        .collect::<Vec<_>>();
+    let line_texcoord_w = mesh
+        .line_texcoord_indices
+        .iter()
+        .filter_map(|&index| mesh.texcoord_w.get(index as usize))
+        .copied()
+        .collect::<Vec<_>>();
+
     // If we have per face per vertex data for UVs ...
     if mesh.positions.len() < mesh.texcoords.len() {
+        if !mesh.texcoord_w.is_empty() {
+            mesh.texcoord_w = mesh
+                .texcoord_indices
+                .iter()
+                .map(|&index| mesh.texcoord_w[index as usize])
+                .collect();
+        }
         mesh.texcoords = mesh
             .texcoord_indices
             .iter()
@@ -1454,18 +4234,35 @@ fn reorder_data(mesh: &mut Mesh) {
             });
 
         mesh.texcoords = new_texcoords;
+
+        if !mesh.texcoord_w.is_empty() {
+            let mut new_texcoord_w = vec![0.0; mesh.positions.len() / 3];
+            mesh.texcoord_indices.iter().zip(&mesh.indices).for_each(
+                |(&texcoord_index, &index)| {
+                    new_texcoord_w[index as usize] = mesh.texcoord_w[texcoord_index as usize];
+                },
+            );
+            mesh.texcoord_w = new_texcoord_w;
+        }
     }
 
     // Clear indices.
     mesh.texcoord_indices = Vec::new();
 
+    let line_texcoord_start = (mesh.texcoords.len() / 2) as u32;
+    mesh.line_texcoord_indices = (line_texcoord_start..)
+        .take(line_texcoords.len() / 2)
+        .collect();
+    mesh.texcoords.extend(line_texcoords);
+    mesh.texcoord_w.extend(line_texcoord_w);
+
     // If we have per face per vertex data for normals ...
     if mesh.positions.len() < mesh.normals.len() {
         mesh.normals = mesh
             .normal_indices
             .iter()
             .flat_map(|&index| {
-                let index = index as usize * 2;
+                let index = index as usize * 3;
                 IntoIterator::into_iter([
                     mesh.normals[index],
                     mesh.normals[index + 1],
@@ -1493,6 +4290,65 @@ fn reorder_data(mesh: &mut Mesh) {
 
     // Clear indices.
     mesh.normal_indices = Vec::new();
+
+    // If we have per face per vertex data for tangents ...
+    #[cfg(feature = "tangents")]
+    if !mesh.tangents.is_empty() {
+        if mesh.positions.len() / 3 < mesh.tangents.len() / 4 {
+            mesh.tangents = mesh
+                .tangent_indices
+                .iter()
+                .flat_map(|&index| {
+                    let index = index as usize * 4;
+                    IntoIterator::into_iter([
+                        mesh.tangents[index],
+                        mesh.tangents[index + 1],
+                        mesh.tangents[index + 2],
+                        mesh.tangents[index + 3],
+                    ])
+                })
+                .collect::<Vec<_>>();
+        } else {
+            let mut new_tangents = vec![0.0; mesh.positions.len() / 3 * 4];
+            mesh.tangent_indices
+                .iter()
+                .zip(&mesh.indices)
+                .for_each(|(&tangent_index, &index)| {
+                    let tangent_index = tangent_index as usize * 4;
+                    let index = index as usize * 4;
+                    new_tangents[index..index + 4]
+                        .copy_from_slice(&mesh.tangents[tangent_index..tangent_index + 4]);
+                });
+
+            mesh.tangents = new_tangents;
+        }
+
+        // Clear indices.
+        mesh.tangent_indices = Vec::new();
+    }
+}
+
+/// Append the `w` component (or alpha) to each of the `N` dimensional
+/// `points`.
+#[cfg(feature = "merging")]
+fn join_w<const N: usize>(points: &[Float], w: &[Float]) -> Vec<Float> {
+    points
+        .chunks_exact(N)
+        .zip(w)
+        .flat_map(|(point, &w)| point.iter().copied().chain([w]))
+        .collect()
+}
+
+/// Split `N` dimensional points with a `w` component into the points and `w`.
+#[cfg(feature = "merging")]
+fn split_w<const N: usize>(points_w: &[Float]) -> (Vec<Float>, Vec<Float>) {
+    let mut points = Vec::with_capacity(points_w.len() / (N + 1) * N);
+    let mut w = Vec::with_capacity(points_w.len() / (N + 1));
+    for point in points_w.chunks_exact(N + 1) {
+        points.extend_from_slice(&point[..N]);
+        w.push(point[N]);
+    }
+    (points, w)
 }
 
 /// Merge identical points. A point has dimension N.
@@ -1545,14 +4401,76 @@ where
 struct TmpModels {
     models: Vec<Model>,
     pos: Vec<Float>,
+    // `w` of each position, empty if no position has one
+    pos_w: Vec<Float>,
     v_color: Vec<Float>,
+    // alpha of each vertex color, empty if no vertex color has one
+    v_alpha: Vec<Float>,
+    // the next vertex to get a color from a `#MRGB` block
+    mrgb_vertex: usize,
     texcoord: Vec<Float>,
+    // `w` of each texture coordinate, empty if no texture coordinate has one
+    texcoord_w: Vec<Float>,
     normal: Vec<Float>,
     faces: Vec<Face>,
+    // attributes of each face in `faces`
+    face_attributes: Vec<FaceAttributes>,
+    // points and polylines and their attributes
+    points: Vec<Face>,
+    point_attributes: Vec<FaceAttributes>,
+    polylines: Vec<Face>,
+    polyline_attributes: Vec<FaceAttributes>,
+    // number of the line being parsed
+    line: usize,
+    warnings: Vec<LoadWarning>,
+    // shapes parsed so far if loading raw shapes instead of models
+    raw_shapes: Option<Vec<RawShape>>,
     // name of the current object being parsed
     name: String,
+    // object and groups of the current object being parsed
+    object: Option<String>,
+    groups: Vec<String>,
     // material used by the current object being parsed
     mat_id: Option<usize>,
+    // smoothing group assigned to faces parsed from now on, `None` until the
+    // first `s` statement
+    smoothing_group: Option<u32>,
+    // free-form geometry of the current object being parsed, its 2D curves
+    // are indices into `curves_2d`
+    free_form: FreeForm,
+    free_form_curves_2d: Vec<usize>,
+    // attributes of each surface in `free_form`, for the faces it is
+    // tessellated into
+    surface_attributes: Vec<FaceAttributes>,
+    // free-form geometry whose body is being parsed, if any
+    free_form_body: Option<FreeFormBody>,
+    // parameter space vertices
+    param_vertices: Vec<[Float; 3]>,
+    // all 2D curves of the file, as trimming loops may refer to curves of
+    // other objects
+    curves_2d: Vec<Curve2D>,
+    // whether each position is used by some geometry
+    referenced: Vec<bool>,
+    // basis assigned to free-form geometry parsed from now on
+    basis_type: Option<(BasisType, bool)>,
+    degree: [u32; 2],
+    basis_matrix: [Vec<Float>; 2],
+    step: [u32; 2],
+}
+
+/// The kinds of free-form geometry with a body of statements up to `end`.
+#[derive(Debug, Clone, Copy)]
+enum FreeFormBody {
+    Curve,
+    Curve2D,
+    Surface,
+}
+
+/// The free-form geometry whose body is being parsed.
+enum FreeFormBodyMut<'a> {
+    Curve(&'a mut Curve),
+    Curve2D(&'a mut Curve2D),
+    Surface(&'a mut Surface),
 }
 
 impl Default for TmpModels {
@@ -1561,12 +4479,38 @@ impl Default for TmpModels {
         Self {
             models: Vec::new(),
             pos: Vec::new(),
+            pos_w: Vec::new(),
             v_color: Vec::new(),
+            v_alpha: Vec::new(),
+            mrgb_vertex: 0,
             texcoord: Vec::new(),
+            texcoord_w: Vec::new(),
             normal: Vec::new(),
             faces: Vec::new(),
+            face_attributes: Vec::new(),
+            points: Vec::new(),
+            point_attributes: Vec::new(),
+            polylines: Vec::new(),
+            polyline_attributes: Vec::new(),
+            line: 0,
+            warnings: Vec::new(),
+            raw_shapes: None,
             name: "unnamed_object".to_owned(),
+            object: None,
+            groups: Vec::new(),
             mat_id: None,
+            smoothing_group: None,
+            free_form: FreeForm::default(),
+            free_form_curves_2d: Vec::new(),
+            surface_attributes: Vec::new(),
+            free_form_body: None,
+            param_vertices: Vec::new(),
+            curves_2d: Vec::new(),
+            referenced: Vec::new(),
+            basis_type: None,
+            degree: [0; 2],
+            basis_matrix: [Vec::new(), Vec::new()],
+            step: [0; 2],
         }
     }
 }
@@ -1579,45 +4523,650 @@ impl TmpModels {
 
     #[inline]
     fn pop_model(&mut self, load_options: &LoadOptions) -> Result<(), LoadError> {
-        self.models.push(Model::new(
-            if load_options.single_index {
-                export_faces(
-                    &self.pos,
-                    &self.v_color,
-                    &self.texcoord,
-                    &self.normal,
-                    &self.faces,
-                    self.mat_id,
-                    load_options,
-                )?
-            } else {
-                export_faces_multi_index(
-                    &self.pos,
-                    &self.v_color,
-                    &self.texcoord,
-                    &self.normal,
-                    &self.faces,
-                    self.mat_id,
-                    load_options,
-                )?
-            },
-            self.name.clone(),
-        ));
+        // With per face materials, the model only has a material if all of its
+        // faces agree on it.
+        let mat_id = match self.face_attributes.split_first() {
+            Some((first, rest)) if load_options.per_face_materials => first
+                .material_id
+                .filter(|_| rest.iter().all(|f| f.material_id == first.material_id)),
+            _ => self.mat_id,
+        };
+        let mut free_form = std::mem::take(&mut self.free_form);
+        free_form.curves_2d = self
+            .free_form_curves_2d
+            .drain(..)
+            .map(|i| self.curves_2d[i].clone())
+            .collect();
+        self.free_form_body = None;
+        let surface_attributes = std::mem::take(&mut self.surface_attributes);
+        if load_options.point_cloud {
+            let vertices = self
+                .faces
+                .iter()
+                .chain(&self.points)
+                .chain(&self.polylines)
+                .flat_map(|f| f.vertices().into_iter().map(|vert| vert.v))
+                .collect::<Vec<_>>();
+            self.reference(vertices);
+        }
+        if self.raw_shapes.is_some() {
+            let mut shape = self.raw_shape(mat_id, load_options)?;
+            shape.free_form = free_form;
+            if let Some(shapes) = &mut self.raw_shapes {
+                shapes.push(shape);
+            }
+            self.faces.clear();
+            self.face_attributes.clear();
+            self.points.clear();
+            self.point_attributes.clear();
+            self.polylines.clear();
+            self.polyline_attributes.clear();
+            return Ok(());
+        }
+        // Tessellated surfaces are only added to the vertex data of the file
+        // while exporting, so relative indices of later faces stay valid
+        let lengths = (
+            self.pos.len(),
+            self.v_color.len(),
+            self.texcoord.len(),
+            self.normal.len(),
+            self.pos_w.len(),
+            self.texcoord_w.len(),
+            self.v_alpha.len(),
+        );
+        if let Some(resolution) = load_options.tessellate_surfaces {
+            self.push_tessellated_surfaces(&free_form.surfaces, &surface_attributes, resolution);
+        }
+        let mesh = if load_options.single_index {
+            export_faces(
+                &self.pos,
+                &self.pos_w,
+                &self.v_color,
+                &self.v_alpha,
+                &self.texcoord,
+                &self.texcoord_w,
+                &self.normal,
+                &self.faces,
+                &self.face_attributes,
+                &self.points,
+                &self.point_attributes,
+                &self.polylines,
+                &self.polyline_attributes,
+                mat_id,
+                load_options,
+                &mut self.warnings,
+            )?
+        } else {
+            export_faces_multi_index(
+                &self.pos,
+                &self.pos_w,
+                &self.v_color,
+                &self.v_alpha,
+                &self.texcoord,
+                &self.texcoord_w,
+                &self.normal,
+                &self.faces,
+                &self.face_attributes,
+                &self.points,
+                &self.point_attributes,
+                &self.polylines,
+                &self.polyline_attributes,
+                mat_id,
+                load_options,
+                &mut self.warnings,
+            )?
+        };
+        self.models.push(Model {
+            mesh,
+            name: self.name.clone(),
+            object: self.object.clone(),
+            groups: self.groups.clone(),
+            free_form,
+        });
         self.faces.clear();
+        self.face_attributes.clear();
+        self.points.clear();
+        self.point_attributes.clear();
+        self.polylines.clear();
+        self.polyline_attributes.clear();
+        self.pos.truncate(lengths.0);
+        self.v_color.truncate(lengths.1);
+        self.texcoord.truncate(lengths.2);
+        self.normal.truncate(lengths.3);
+        self.pos_w.truncate(lengths.4);
+        self.texcoord_w.truncate(lengths.5);
+        self.v_alpha.truncate(lengths.6);
+        Ok(())
+    }
+
+    /// Add the triangles of the tessellated `surfaces` to the faces parsed
+    /// since the last model, along with their vertices.
+    fn push_tessellated_surfaces(
+        &mut self,
+        surfaces: &[Surface],
+        surface_attributes: &[FaceAttributes],
+        resolution: u32,
+    ) {
+        for (surface, attributes) in surfaces.iter().zip(surface_attributes) {
+            let Some(mesh) = surface.tessellate(resolution) else {
+                self.warnings.push(LoadWarning::UntessellatedSurface {
+                    line: attributes.line,
+                });
+                continue;
+            };
+            let (v, vt, vn) = (
+                self.pos.len() / 3,
+                self.texcoord.len() / 2,
+                self.normal.len() / 3,
+            );
+            // Surfaces have no vertex colors, use white if other vertices do
+            if self.v_color.len() == self.pos.len() && !self.v_color.is_empty() {
+                self.v_color
+                    .resize(self.v_color.len() + mesh.positions.len(), 1.0);
+            }
+            self.pos.extend_from_slice(&mesh.positions);
+            self.texcoord.extend_from_slice(&mesh.texcoords);
+            self.normal.extend_from_slice(&mesh.normals);
+            if !self.pos_w.is_empty() {
+                self.pos_w.resize(self.pos.len() / 3, 1.0);
+            }
+            if !self.v_alpha.is_empty() {
+                self.v_alpha.resize(self.pos.len() / 3, 1.0);
+            }
+            if !self.texcoord_w.is_empty() {
+                self.texcoord_w.resize(self.texcoord.len() / 2, 0.0);
+            }
+            let vertex = |i: u32| VertexIndices {
+                v: v + i as usize,
+                vt: if mesh.texcoords.is_empty() {
+                    MISSING_INDEX
+                } else {
+                    vt + i as usize
+                },
+                vn: vn + i as usize,
+            };
+            for triangle in mesh.indices.chunks(3) {
+                self.faces.push(Face::Triangle(
+                    vertex(triangle[0]),
+                    vertex(triangle[1]),
+                    vertex(triangle[2]),
+                ));
+                self.face_attributes.push(*attributes);
+            }
+        }
+    }
+
+    /// Pop the last model of the file, followed by the point cloud of the
+    /// vertices no geometry uses if
+    /// [`point_cloud`](LoadOptions::point_cloud) is set.
+    fn finish(&mut self, load_options: &LoadOptions) -> Result<(), LoadError> {
+        let has_geometry = self.has_geometry();
+        if has_geometry || !load_options.point_cloud {
+            self.pop_model(load_options)?;
+        }
+        if !load_options.point_cloud {
+            return Ok(());
+        }
+
+        let vertex_count = self.pos.len() / 3;
+        self.referenced.resize(vertex_count, false);
+        let unreferenced = (0..vertex_count)
+            .filter(|&v| !self.referenced[v])
+            .collect::<Vec<_>>();
+        if unreferenced.is_empty() {
+            if !has_geometry {
+                self.pop_model(load_options)?;
+            }
+            return Ok(());
+        }
+        let has_vertex_color = self.v_color.len() == self.pos.len();
+        let has_normals = self.normal.len() == self.pos.len();
+        // The point cloud only takes the name and state of the last model if
+        // it takes its place, its vertices may come from anywhere in the file
+        let (name, object, groups, mat_id) = if has_geometry {
+            ("unnamed_object".to_owned(), None, Vec::new(), None)
+        } else {
+            (
+                self.name.clone(),
+                self.object.clone(),
+                self.groups.clone(),
+                self.mat_id,
+            )
+        };
+
+        if let Some(shapes) = &mut self.raw_shapes {
+            shapes.push(RawShape {
+                name,
+                object,
+                groups,
+                point_indices: unreferenced
+                    .into_iter()
+                    .map(|v| VertexIndices {
+                        v,
+                        vt: MISSING_INDEX,
+                        vn: if has_normals { v } else { MISSING_INDEX },
+                    })
+                    .collect(),
+                material_id: mat_id,
+                ..Default::default()
+            });
+            return Ok(());
+        }
+
+        let mut mesh = Mesh {
+            point_indices: (0..unreferenced.len() as u32).collect(),
+            material_id: mat_id,
+            ..Default::default()
+        };
+        for v in unreferenced {
+            mesh.positions
+                .extend_from_slice(&self.pos[v * 3..v * 3 + 3]);
+            if load_options.preserve_w {
+                mesh.position_w.extend(self.pos_w.get(v));
+            }
+            if has_vertex_color {
+                mesh.vertex_color
+                    .extend_from_slice(&self.v_color[v * 3..v * 3 + 3]);
+                mesh.vertex_color_alpha.extend(self.v_alpha.get(v));
+            }
+            if has_normals {
+                mesh.normals
+                    .extend_from_slice(&self.normal[v * 3..v * 3 + 3]);
+            }
+        }
+        let mut model = Model::new(mesh, name);
+        model.object = object;
+        model.groups = groups;
+        self.models.push(model);
         Ok(())
     }
 
+    /// Add the position of a `v` statement from its values, which are `None`
+    /// if they are not a number.
+    ///
+    /// A fourth value is the `w` of the position, three or four more values are
+    /// an inline vertex color with an optional alpha.
+    fn push_position(
+        &mut self,
+        values: impl Iterator<Item = Option<Float>>,
+    ) -> Result<(), LoadErrorKind> {
+        let mut vals = [0.0; 7];
+        let mut n = 0;
+        for (val, value) in vals.iter_mut().zip(values.map_while(|value| value)) {
+            *val = value;
+            n += 1;
+        }
+        if n < 3 {
+            return Err(LoadErrorKind::PositionParseError);
+        }
+        self.pos.extend_from_slice(&vals[..3]);
+
+        let (w, color, alpha) = match vals[3..n] {
+            [w] => (Some(w), None, None),
+            [r, g, b] => (None, Some([r, g, b]), None),
+            [r, g, b, a] => (None, Some([r, g, b]), Some(a)),
+            _ => (None, None, None),
+        };
+        let count = self.pos.len() / 3;
+        // Vertices without a color are white if others have one
+        push_aligned(
+            &mut self.pos_w,
+            w.as_ref().map(std::slice::from_ref),
+            count,
+            1.0,
+        );
+        push_aligned(
+            &mut self.v_color,
+            color.as_ref().map(|c| &c[..]),
+            count,
+            1.0,
+        );
+        push_aligned(
+            &mut self.v_alpha,
+            alpha.as_ref().map(std::slice::from_ref),
+            count,
+            1.0,
+        );
+        Ok(())
+    }
+
+    /// Add zeros in place of the vertex data of a skipped `v`, `vt`, `vn` or
+    /// `vp` statement, so the following ones keep their indices.
+    fn push_placeholder(&mut self, keyword: &str) {
+        let zeros = [Some(0.0); 3].into_iter();
+        // Three values are enough for any of them, so this can't fail
+        let result = match keyword {
+            "v" => self.push_position(zeros),
+            "vt" => self.push_texcoord(zeros),
+            "vn" => self.push_normal(zeros),
+            "vp" => {
+                self.param_vertices.push([0.0, 0.0, 1.0]);
+                Ok(())
+            }
+            _ => Ok(()),
+        };
+        debug_assert!(result.is_ok());
+    }
+
+    /// Add the texture coordinate of a `vt` statement from its values, see
+    /// [`push_position()`](Self::push_position).
+    fn push_texcoord(
+        &mut self,
+        values: impl Iterator<Item = Option<Float>>,
+    ) -> Result<(), LoadErrorKind> {
+        let mut values = values.map_while(|value| value);
+        let (Some(u), Some(v)) = (values.next(), values.next()) else {
+            return Err(LoadErrorKind::TexcoordParseError);
+        };
+        self.texcoord.extend([u, v]);
+        push_aligned(
+            &mut self.texcoord_w,
+            values.next().as_ref().map(std::slice::from_ref),
+            self.texcoord.len() / 2,
+            0.0,
+        );
+        Ok(())
+    }
+
+    /// Add the normal of a `vn` statement from its values, see
+    /// [`push_position()`](Self::push_position).
+    fn push_normal(
+        &mut self,
+        values: impl Iterator<Item = Option<Float>>,
+    ) -> Result<(), LoadErrorKind> {
+        let mut values = values.map_while(|value| value);
+        let (Some(x), Some(y), Some(z)) = (values.next(), values.next(), values.next()) else {
+            return Err(LoadErrorKind::NormalParseError);
+        };
+        self.normal.extend([x, y, z]);
+        Ok(())
+    }
+
+    /// The attributes of a face, point or polyline on the current line.
+    fn face_attributes(&self) -> FaceAttributes {
+        FaceAttributes {
+            line: self.line,
+            smoothing_group: self.smoothing_group,
+            material_id: self.mat_id,
+        }
+    }
+
+    /// Add the face of an `f` statement from its vertices, a face with a
+    /// single vertex is a point.
+    fn push_face<'a>(
+        &mut self,
+        vertices: impl Iterator<Item = &'a [u8]> + Clone,
+    ) -> Result<(), LoadError> {
+        let attributes = self.face_attributes();
+        // Faces with a single vertex are points
+        let (faces, face_attributes) = if vertices.clone().count() == 1 {
+            (&mut self.points, &mut self.point_attributes)
+        } else {
+            (&mut self.faces, &mut self.face_attributes)
+        };
+        parse_face(
+            vertices,
+            faces,
+            self.pos.len() / 3,
+            self.texcoord.len() / 2,
+            self.normal.len() / 3,
+        )?;
+        face_attributes.push(attributes);
+        Ok(())
+    }
+
+    /// Add the points of a `p` statement from its vertices.
+    fn push_points<'a>(
+        &mut self,
+        vertices: impl Iterator<Item = &'a [u8]>,
+    ) -> Result<(), LoadError> {
+        let attributes = self.face_attributes();
+        let (pos_sz, tex_sz, norm_sz) = (
+            self.pos.len() / 3,
+            self.texcoord.len() / 2,
+            self.normal.len() / 3,
+        );
+        let points = vertices
+            .map(|vertex| {
+                VertexIndices::parse(vertex, pos_sz, tex_sz, norm_sz)
+                    .map(Face::Point)
+                    .ok_or_else(|| face_parse_error(vertex))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        if points.is_empty() {
+            return Err(LoadErrorKind::FaceParseError.into());
+        }
+        self.points.extend(points);
+        self.point_attributes.resize(self.points.len(), attributes);
+        Ok(())
+    }
+
+    /// Add the polyline of an `l` statement from its vertices.
+    fn push_polyline<'a>(
+        &mut self,
+        vertices: impl Iterator<Item = &'a [u8]> + Clone,
+    ) -> Result<(), LoadError> {
+        if vertices.clone().count() < 2 {
+            return Err(LoadErrorKind::FaceParseError.into());
+        }
+        parse_face(
+            vertices,
+            &mut self.polylines,
+            self.pos.len() / 3,
+            self.texcoord.len() / 2,
+            self.normal.len() / 3,
+        )?;
+        self.polyline_attributes.push(self.face_attributes());
+        Ok(())
+    }
+
+    /// Set the colors of the next vertices from the `MMRRGGBB` hex values of a
+    /// ZBrush `#MRGB` block, ignoring the mask `MM`. Like for comments, values
+    /// that are invalid or for vertices that don't exist yet are ignored.
+    fn push_mrgb(&mut self, words: SplitWhitespace) {
+        let vertex_count = self.pos.len() / 3;
+        for word in words {
+            for value in word.as_bytes().chunks(8) {
+                let Some(value) = std::str::from_utf8(value)
+                    .ok()
+                    .filter(|value| value.len() == 8)
+                    .and_then(|value| u32::from_str_radix(value, 16).ok())
+                else {
+                    return;
+                };
+                let v = self.mrgb_vertex;
+                if v >= vertex_count {
+                    return;
+                }
+                if self.v_color.is_empty() {
+                    self.v_color.resize(self.pos.len(), 1.0);
+                }
+                let color = [16, 8, 0].map(|shift| ((value >> shift) & 0xff) as Float / 255.0);
+                self.v_color[v * 3..v * 3 + 3].copy_from_slice(&color);
+                self.mrgb_vertex += 1;
+            }
+        }
+    }
+
+    /// Mark the positions `vertices` as used by some geometry.
+    fn reference(&mut self, vertices: impl IntoIterator<Item = usize>) {
+        let vertex_count = self.pos.len() / 3;
+        for v in vertices {
+            if v < vertex_count {
+                if v >= self.referenced.len() {
+                    self.referenced.resize(vertex_count, false);
+                }
+                self.referenced[v] = true;
+            }
+        }
+    }
+
+    /// Whether any faces or free-form geometry were parsed since the last
+    /// model.
+    fn has_geometry(&self) -> bool {
+        !self.faces.is_empty()
+            || !self.points.is_empty()
+            || !self.polylines.is_empty()
+            || !self.free_form.is_empty()
+            || !self.free_form_curves_2d.is_empty()
+    }
+
+    /// The position `v` as a control point of free-form geometry, with its `w`
+    /// as the weight.
+    fn control_point(&self, v: usize) -> [Float; 4] {
+        let p = &self.pos[v * 3..v * 3 + 3];
+        [p[0], p[1], p[2], self.pos_w.get(v).copied().unwrap_or(1.0)]
+    }
+
+    /// The basis for free-form geometry parsed now.
+    fn basis(&self) -> Result<Basis, LoadErrorKind> {
+        let (basis_type, rational) = self.basis_type.ok_or(LoadErrorKind::FreeFormParseError)?;
+        Ok(Basis {
+            basis_type,
+            rational,
+            degree: self.degree,
+            matrix: self.basis_matrix.clone(),
+            step: self.step,
+        })
+    }
+
+    /// The free-form geometry whose body is being parsed.
+    fn free_form_body(&mut self) -> Result<FreeFormBodyMut<'_>, LoadErrorKind> {
+        match self.free_form_body {
+            Some(FreeFormBody::Curve) => {
+                self.free_form.curves.last_mut().map(FreeFormBodyMut::Curve)
+            }
+            Some(FreeFormBody::Curve2D) => self.curves_2d.last_mut().map(FreeFormBodyMut::Curve2D),
+            Some(FreeFormBody::Surface) => self
+                .free_form
+                .surfaces
+                .last_mut()
+                .map(FreeFormBodyMut::Surface),
+            None => None,
+        }
+        .ok_or(LoadErrorKind::FreeFormParseError)
+    }
+
+    /// Create a raw shape of the faces parsed since the last model.
+    fn raw_shape(
+        &mut self,
+        mat_id: Option<usize>,
+        load_options: &LoadOptions,
+    ) -> Result<RawShape, LoadError> {
+        let mut shape = RawShape {
+            name: self.name.clone(),
+            object: self.object.clone(),
+            groups: self.groups.clone(),
+            material_id: mat_id,
+            ..Default::default()
+        };
+        let mut has_smoothing_groups = false;
+        for (f, attributes) in self.faces.iter().zip(&self.face_attributes) {
+            if let Err(kind) =
+                f.check_bounds(&self.pos, &self.v_color, &self.texcoord, &self.normal)
+            {
+                let e = LoadError::new(kind).at_line(attributes.line, Some("f"));
+                if !load_options.skip_invalid_lines {
+                    return Err(e);
+                }
+                self.warnings.push(LoadWarning::SkippedLine(e));
+                continue;
+            }
+            let vertices = f.vertices();
+            shape.face_arities.push(vertices.len() as u32);
+            shape.indices.extend(vertices);
+            shape
+                .smoothing_groups
+                .push(attributes.smoothing_group.unwrap_or(0));
+            has_smoothing_groups |= attributes.smoothing_group.is_some();
+            if load_options.per_face_materials {
+                shape.face_material_ids.push(attributes.material_id);
+            }
+        }
+        if !has_smoothing_groups {
+            shape.smoothing_groups = Vec::new();
+        }
+        for (point, attributes) in self.points.iter().zip(&self.point_attributes) {
+            if let Err(kind) =
+                point.check_bounds(&self.pos, &self.v_color, &self.texcoord, &self.normal)
+            {
+                let e = LoadError::new(kind).at_line(attributes.line, None);
+                if !load_options.skip_invalid_lines {
+                    return Err(e);
+                }
+                self.warnings.push(LoadWarning::SkippedLine(e));
+                continue;
+            }
+            shape.point_indices.extend(point.vertices());
+        }
+        for (polyline, attributes) in self.polylines.iter().zip(&self.polyline_attributes) {
+            if let Err(kind) =
+                polyline.check_bounds(&self.pos, &self.v_color, &self.texcoord, &self.normal)
+            {
+                let e = LoadError::new(kind).at_line(attributes.line, Some("l"));
+                if !load_options.skip_invalid_lines {
+                    return Err(e);
+                }
+                self.warnings.push(LoadWarning::SkippedLine(e));
+                continue;
+            }
+            let vertices = polyline.vertices();
+            shape.line_arities.push(vertices.len() as u32);
+            shape.line_indices.extend(vertices);
+        }
+        Ok(shape)
+    }
+
+    #[inline]
+    fn into_load_result(mut self, mut materials: TmpMaterials) -> LoadResult {
+        self.warnings.append(&mut materials.warnings);
+        Ok((self.models, materials.into_materials(), self.warnings))
+    }
+
     #[inline]
-    fn into_models(self) -> Vec<Model> {
-        self.models
+    fn into_raw_load_result(
+        mut self,
+        mut materials: TmpMaterials,
+        load_options: &LoadOptions,
+    ) -> RawLoadResult {
+        self.warnings.append(&mut materials.warnings);
+        if !load_options.preserve_w {
+            self.pos_w.clear();
+            self.texcoord_w.clear();
+        }
+        let raw = RawObj {
+            positions: self.pos,
+            position_w: self.pos_w,
+            vertex_color: self.v_color,
+            vertex_color_alpha: self.v_alpha,
+            texcoords: self.texcoord,
+            texcoord_w: self.texcoord_w,
+            normals: self.normal,
+            shapes: self.raw_shapes.unwrap_or_default(),
+        };
+        Ok((raw, materials.into_materials(), self.warnings))
     }
 }
 
+/// Attributes of a face in the `OBJ` file that are shared by all faces it is
+/// exported as.
+#[derive(Debug, Clone, Copy)]
+struct FaceAttributes {
+    // line the face was read from
+    line: usize,
+    // `None` until the first `s` statement
+    smoothing_group: Option<u32>,
+    material_id: Option<usize>,
+}
+
 #[derive(Debug)]
 struct TmpMaterials {
     materials: Vec<Material>,
     mat_map: HashMap<String, usize>,
     mtlerr: Option<LoadError>,
+    // number of the line being parsed
+    line: usize,
+    warnings: Vec<LoadWarning>,
 }
 
 impl Default for TmpMaterials {
@@ -1627,6 +5176,8 @@ impl Default for TmpMaterials {
             materials: Vec::new(),
             mat_map: HashMap::new(),
             mtlerr: None,
+            line: 0,
+            warnings: Vec::new(),
         }
     }
 }
@@ -1647,7 +5198,8 @@ impl TmpMaterials {
     #[inline]
     fn merge(&mut self, mtl_load_result: MTLLoadResult) {
         match mtl_load_result {
-            Ok((mut mats, map)) => {
+            Ok((mut mats, map, mut warnings)) => {
+                self.warnings.append(&mut warnings);
                 // Merge the loaded material lib with any currently loaded ones,
                 // offsetting the indices of the appended
                 // materials by our current length
@@ -1665,7 +5217,25 @@ impl TmpMaterials {
 
     #[inline]
     fn into_mtl_load_result(self) -> MTLLoadResult {
-        Ok((self.materials, self.mat_map))
+        Ok((self.materials, self.mat_map, self.warnings))
+    }
+
+    /// Parse the texture map of a `MTL` statement, options with invalid values
+    /// are skipped with a warning.
+    fn texture_map(
+        &mut self,
+        material: &str,
+        keyword: &str,
+        raw: &str,
+    ) -> Result<TextureMap, LoadError> {
+        let (texture, valid) = TextureMap::parse_lenient(raw)?;
+        if !valid {
+            self.warnings.push(LoadWarning::InvalidTextureOptions {
+                material: material.to_owned(),
+                keyword: keyword.to_owned(),
+            });
+        }
+        Ok(texture)
     }
 
     #[inline]
@@ -1687,122 +5257,513 @@ enum ParseReturnType {
 
 #[inline]
 fn parse_obj_line(
-    line: std::io::Result<String>,
+    line: std::io::Result<impl AsRef<[u8]>>,
     load_options: &LoadOptions,
     models: &mut TmpModels,
     materials: &TmpMaterials,
 ) -> Result<ParseReturnType, LoadError> {
-    let (line, mut words) = match line {
-        Ok(ref line) => (&line[..], line[..].split_whitespace()),
-        Err(_e) => {
+    models.line += 1;
+    let line_number = models.line;
+    let read_error = |e: std::io::Error| {
+        #[cfg(feature = "log")]
+        log::error!("load_obj - failed to read line due to {}", e);
+        LoadError::new(LoadErrorKind::ReadError)
+            .with_io_error(e)
+            .at_line(line_number, None)
+    };
+    let result = match line {
+        Ok(line) => match parse_geometry_statement(line.as_ref(), models) {
+            Some(result) => result.map(|()| ParseReturnType::None),
+            // Invalid UTF-8 is reported like `BufRead::lines()` does
+            None => match std::str::from_utf8(line.as_ref()) {
+                Ok(line) => parse_obj_directive(line, load_options, models, materials)
+                    .map_err(|e| e.at_line(line_number, line.split_whitespace().next())),
+                Err(e) => Err(read_error(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    e,
+                ))),
+            },
+        },
+        Err(e) => Err(read_error(e)),
+    };
+    match result {
+        // Lines that are not valid UTF-8 are consumed by the reader, other read
+        // errors may not be recoverable.
+        Err(e)
+            if load_options.skip_invalid_lines
+                && !matches!(
+                    e.io_error(),
+                    Some(e) if e.kind() != std::io::ErrorKind::InvalidData
+                ) =>
+        {
             #[cfg(feature = "log")]
-            log::error!("load_obj - failed to read line due to {}", _e);
-            return Err(LoadError::ReadError);
+            log::warn!("load_obj - skipping line: {}", e);
+            if let Some(keyword) = e.token() {
+                models.push_placeholder(keyword);
+            }
+            models.warnings.push(LoadWarning::SkippedLine(e));
+            Ok(ParseReturnType::None)
         }
+        result => result,
+    }
+}
+
+/// Parse a `v`, `vt`, `vn`, `f`, `l` or `p` statement directly from the bytes
+/// of the line, returns `None` for other statements.
+///
+/// These make up most of large files, so they skip the UTF-8 validation and
+/// parse their numbers with [`parse_float_bytes()`] and
+/// [`parse_index_bytes()`]. Lines that aren't ASCII are left to
+/// [`parse_obj_directive()`], which splits them at any whitespace.
+#[inline]
+fn parse_geometry_statement(line: &[u8], models: &mut TmpModels) -> Option<Result<(), LoadError>> {
+    if !line.is_ascii() {
+        return None;
+    }
+    // The ASCII characters `char::is_whitespace()` accepts
+    let mut words = line
+        .split(|byte| byte.is_ascii_whitespace() || *byte == b'\x0b')
+        .filter(|word| !word.is_empty());
+    let keyword = words.next()?;
+    let values = words.clone().map(parse_float_bytes);
+    let result = match keyword {
+        b"v" => models.push_position(values).map_err(LoadError::from),
+        b"vt" => models.push_texcoord(values).map_err(LoadError::from),
+        b"vn" => models.push_normal(values).map_err(LoadError::from),
+        b"f" => models.push_face(words),
+        b"p" => models.push_points(words),
+        b"l" => models.push_polyline(words),
+        _ => return None,
     };
+    let line = models.line;
+    Some(result.map_err(|e| e.at_line(line, std::str::from_utf8(keyword).ok())))
+}
+
+#[inline]
+fn parse_obj_directive(
+    line: &str,
+    load_options: &LoadOptions,
+    models: &mut TmpModels,
+    materials: &TmpMaterials,
+) -> Result<ParseReturnType, LoadError> {
+    let mut words = line.split_whitespace();
     match words.next() {
         Some("#") | None => Ok(ParseReturnType::None),
+        Some("#MRGB") => {
+            models.push_mrgb(words);
+            Ok(ParseReturnType::None)
+        }
         Some("v") => {
-            if !parse_floatn(&mut words, &mut models.pos, 3) {
-                return Err(LoadError::PositionParseError);
-            }
-
-            // Add inline vertex colors if present.
-            parse_floatn(&mut words, &mut models.v_color, 3);
+            models.push_position(words.map(|word| word.parse().ok()))?;
             Ok(ParseReturnType::None)
         }
         Some("vt") => {
-            if !parse_floatn(&mut words, &mut models.texcoord, 2) {
-                Err(LoadError::TexcoordParseError)
+            models.push_texcoord(words.map(|word| word.parse().ok()))?;
+            Ok(ParseReturnType::None)
+        }
+        Some("vn") => {
+            models.push_normal(words.map(|word| word.parse().ok()))?;
+            Ok(ParseReturnType::None)
+        }
+        Some("f") => models
+            .push_face(words.map(str::as_bytes))
+            .map(|()| ParseReturnType::None),
+        Some("p") => models
+            .push_points(words.map(str::as_bytes))
+            .map(|()| ParseReturnType::None),
+        Some("l") => models
+            .push_polyline(words.map(str::as_bytes))
+            .map(|()| ParseReturnType::None),
+        Some("s") => {
+            // `s off` and `s 0` both take subsequent faces out of any smoothing group
+            let group = match words.next() {
+                Some("off") => Some(0),
+                group => group.and_then(|group| u32::from_str(group).ok()),
+            };
+            // Invalid smoothing groups are taken as `off` as well
+            if group.is_none() {
+                models
+                    .warnings
+                    .push(LoadWarning::InvalidSmoothingGroup { line: models.line });
+            }
+            models.smoothing_group = Some(group.unwrap_or(0));
+            Ok(ParseReturnType::None)
+        }
+        // Objects and groups both start a new model and name it, but are
+        // tracked separately as well
+        Some(keyword @ ("o" | "g")) => {
+            // If we were already parsing an object then a new object name
+            // signals the end of the current one, so push it onto our list of objects
+            if models.has_geometry() {
+                models.pop_model(load_options)?;
+            }
+            let size = line.chars().next().unwrap().len_utf8();
+            let name = line[size..].trim();
+            if keyword == "o" {
+                // Object names can include spaces, group names are separated by them
+                models.object = Some(name.to_owned()).filter(|name| !name.is_empty());
+                models.groups.clear();
             } else {
+                models.groups = words.map(ToOwned::to_owned).collect();
+            }
+            models.name = name.to_owned();
+            if models.name.is_empty() {
+                models.name = "unnamed_object".to_owned();
+            }
+            Ok(ParseReturnType::None)
+        }
+        Some(
+            keyword @ ("vp" | "cstype" | "deg" | "bmat" | "step" | "curv" | "curv2" | "surf"
+            | "parm" | "trim" | "hole" | "scrv" | "sp" | "end"),
+        ) => {
+            parse_free_form(keyword, words, models)?;
+            Ok(ParseReturnType::None)
+        }
+        Some("mtllib") => {
+            // File name can include spaces so we cannot rely on a SplitWhitespace iterator
+            let mtllib = line.split_once(' ').unwrap_or_default().1.trim();
+            let mat_file = Path::new(mtllib).to_path_buf();
+            Ok(ParseReturnType::LoadMaterial(mat_file))
+        }
+        Some("usemtl") => {
+            let mat_name = line.split_once(' ').unwrap_or_default().1.trim().to_owned();
+
+            if !mat_name.is_empty() {
+                let new_mat = materials.mat_map.get(&mat_name).cloned();
+                // As materials are returned per-model, a new material within an object
+                // has to emit a new model with the same name but different material,
+                // unless materials are kept per face
+                if models.mat_id != new_mat
+                    && models.has_geometry()
+                    && !load_options.per_face_materials
+                {
+                    models.pop_model(load_options)?;
+                }
+                if new_mat.is_none() && mat_name != NO_MATERIAL {
+                    #[cfg(feature = "log")]
+                    log::warn!(
+                        "Object {} refers to unfound material: {}",
+                        models.name,
+                        mat_name
+                    );
+                    models.warnings.push(LoadWarning::UnresolvedMaterial {
+                        line: models.line,
+                        object: models.name.clone(),
+                        material: mat_name,
+                    });
+                }
+                models.mat_id = new_mat;
                 Ok(ParseReturnType::None)
+            } else {
+                Err(LoadErrorKind::MaterialParseError.into())
             }
         }
-        Some("vn") => {
-            if !parse_floatn(&mut words, &mut models.normal, 3) {
-                Err(LoadError::NormalParseError)
+        // Just ignore unrecognized characters, comments without a space after
+        // the `#` included
+        Some(directive) => {
+            if !directive.starts_with('#') {
+                models.warnings.push(LoadWarning::IgnoredDirective {
+                    line: models.line,
+                    directive: directive.to_owned(),
+                });
+            }
+            Ok(ParseReturnType::None)
+        }
+    }
+}
+
+/// Parse the values of a free-form geometry statement.
+fn parse_values<'a, T: FromStr>(
+    words: impl Iterator<Item = &'a str>,
+) -> Result<Vec<T>, LoadErrorKind> {
+    words
+        .map(|word| T::from_str(word).map_err(|_| LoadErrorKind::FreeFormParseError))
+        .collect()
+}
+
+/// Resolve a 1-based or relative (negative) index into a list of `len`
+/// elements.
+///
+/// Returns `None` if the index is invalid or out of bounds.
+fn resolve_index(word: &str, len: usize) -> Option<usize> {
+    let index = isize::from_str(word).ok()?;
+    let index = if index < 0 {
+        len.checked_sub(index.unsigned_abs())?
+    } else {
+        (index as usize).checked_sub(1)?
+    };
+    (index < len).then_some(index)
+}
+
+/// Parse the `u` or `v` direction of a free-form geometry statement.
+fn parse_direction(word: Option<&str>) -> Result<usize, LoadErrorKind> {
+    match word {
+        Some("u") => Ok(0),
+        Some("v") => Ok(1),
+        _ => Err(LoadErrorKind::FreeFormParseError),
+    }
+}
+
+/// Parse the curve segments of a `trim`, `hole` or `scrv` statement.
+fn parse_curve_segments(
+    words: SplitWhitespace,
+    curves_2d: &[Curve2D],
+) -> Result<Vec<CurveSegment>, LoadErrorKind> {
+    let words = words.collect::<Vec<_>>();
+    if words.is_empty() || words.len() % 3 != 0 {
+        return Err(LoadErrorKind::FreeFormParseError);
+    }
+    words
+        .chunks(3)
+        .map(|segment| {
+            let parameter_range = parse_values(segment[..2].iter().copied())?;
+            let curve = resolve_index(segment[2], curves_2d.len())
+                .ok_or(LoadErrorKind::FreeFormParseError)?;
+            Ok(CurveSegment {
+                parameter_range: [parameter_range[0], parameter_range[1]],
+                curve: curves_2d[curve].clone(),
+            })
+        })
+        .collect()
+}
+
+/// Parse a free-form geometry statement.
+///
+/// Curves and surfaces are added to the current model, the statements of
+/// their body up to `end` apply to the last one added.
+fn parse_free_form(
+    keyword: &str,
+    mut words: SplitWhitespace,
+    models: &mut TmpModels,
+) -> Result<(), LoadErrorKind> {
+    match keyword {
+        "vp" => {
+            let vals = parse_values(words)?;
+            if vals.is_empty() || vals.len() > 3 {
+                return Err(LoadErrorKind::FreeFormParseError);
+            }
+            models.param_vertices.push([
+                vals[0],
+                vals.get(1).copied().unwrap_or(0.0),
+                vals.get(2).copied().unwrap_or(1.0),
+            ]);
+        }
+        "cstype" => {
+            let mut basis_type = words.next();
+            let rational = basis_type == Some("rat");
+            if rational {
+                basis_type = words.next();
+            }
+            let basis_type = match basis_type {
+                Some("bmatrix") => BasisType::BasisMatrix,
+                Some("bezier") => BasisType::Bezier,
+                Some("bspline") => BasisType::BSpline,
+                Some("cardinal") => BasisType::Cardinal,
+                Some("taylor") => BasisType::Taylor,
+                _ => return Err(LoadErrorKind::FreeFormParseError),
+            };
+            models.basis_type = Some((basis_type, rational));
+        }
+        "deg" | "step" => {
+            let vals = parse_values(words)?;
+            if vals.is_empty() || vals.len() > 2 {
+                return Err(LoadErrorKind::FreeFormParseError);
+            }
+            let vals = [vals[0], vals.get(1).copied().unwrap_or(0)];
+            if keyword == "deg" {
+                models.degree = vals;
             } else {
-                Ok(ParseReturnType::None)
+                models.step = vals;
+            }
+        }
+        "bmat" => {
+            let direction = parse_direction(words.next())?;
+            models.basis_matrix[direction] = parse_values(words)?;
+        }
+        "curv" => {
+            let basis = models.basis()?;
+            let vals = words.collect::<Vec<_>>();
+            if vals.len() < 4 {
+                return Err(LoadErrorKind::FreeFormParseError);
+            }
+            let parameter_range = parse_values(vals[..2].iter().copied())?;
+            let vertices = vals[2..]
+                .iter()
+                .map(|word| {
+                    resolve_index(word, models.pos.len() / 3)
+                        .ok_or(LoadErrorKind::FreeFormParseError)
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            let control_points = vertices.iter().map(|&v| models.control_point(v)).collect();
+            models.reference(vertices);
+            models.free_form.curves.push(Curve {
+                basis,
+                parameter_range: [parameter_range[0], parameter_range[1]],
+                control_points,
+                knots: Vec::new(),
+                special_points: Vec::new(),
+            });
+            models.free_form_body = Some(FreeFormBody::Curve);
+        }
+        "curv2" => {
+            let basis = models.basis()?;
+            let control_points = words
+                .map(|word| {
+                    resolve_index(word, models.param_vertices.len())
+                        .map(|vp| models.param_vertices[vp])
+                        .ok_or(LoadErrorKind::FreeFormParseError)
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            if control_points.len() < 2 {
+                return Err(LoadErrorKind::FreeFormParseError);
             }
+            models.free_form_curves_2d.push(models.curves_2d.len());
+            models.curves_2d.push(Curve2D {
+                basis,
+                control_points,
+                knots: Vec::new(),
+                special_points: Vec::new(),
+            });
+            models.free_form_body = Some(FreeFormBody::Curve2D);
         }
-        Some("f") | Some("l") => {
-            if !parse_face(
-                words,
-                &mut models.faces,
+        "surf" => {
+            let basis = models.basis()?;
+            let vals = words.collect::<Vec<_>>();
+            if vals.len() < 6 {
+                return Err(LoadErrorKind::FreeFormParseError);
+            }
+            let parameter_range = parse_values(vals[..4].iter().copied())?;
+            let mut surface = Surface {
+                basis,
+                parameter_range: [
+                    [parameter_range[0], parameter_range[1]],
+                    [parameter_range[2], parameter_range[3]],
+                ],
+                control_points: Vec::new(),
+                texcoords: Vec::new(),
+                normals: Vec::new(),
+                knots: [Vec::new(), Vec::new()],
+                trims: Vec::new(),
+                holes: Vec::new(),
+                special_curves: Vec::new(),
+                special_points: Vec::new(),
+            };
+            let (pos_sz, tex_sz, norm_sz) = (
                 models.pos.len() / 3,
                 models.texcoord.len() / 2,
                 models.normal.len() / 3,
-            ) {
-                Err(LoadError::FaceParseError)
-            } else {
-                Ok(ParseReturnType::None)
+            );
+            for word in &vals[4..] {
+                let vert = VertexIndices::parse(word.as_bytes(), pos_sz, tex_sz, norm_sz)
+                    .filter(|vert| vert.v < pos_sz)
+                    .ok_or(LoadErrorKind::FreeFormParseError)?;
+                surface.control_points.push(models.control_point(vert.v));
+                models.reference([vert.v]);
+                if vert.vt != MISSING_INDEX {
+                    let t = models
+                        .texcoord
+                        .get(vert.vt * 2..vert.vt * 2 + 2)
+                        .ok_or(LoadErrorKind::FreeFormParseError)?;
+                    surface.texcoords.push([t[0], t[1]]);
+                }
+                if vert.vn != MISSING_INDEX {
+                    let n = models
+                        .normal
+                        .get(vert.vn * 3..vert.vn * 3 + 3)
+                        .ok_or(LoadErrorKind::FreeFormParseError)?;
+                    surface.normals.push([n[0], n[1], n[2]]);
+                }
+            }
+            if surface.texcoords.len() != surface.control_points.len() {
+                surface.texcoords = Vec::new();
             }
+            if surface.normals.len() != surface.control_points.len() {
+                surface.normals = Vec::new();
+            }
+            models.free_form.surfaces.push(surface);
+            models.surface_attributes.push(FaceAttributes {
+                line: models.line,
+                smoothing_group: models.smoothing_group,
+                material_id: models.mat_id,
+            });
+            models.free_form_body = Some(FreeFormBody::Surface);
         }
-        // Just treating object and group tags identically. Should there be different behavior
-        // for them?
-        Some("o") | Some("g") => {
-            // If we were already parsing an object then a new object name
-            // signals the end of the current one, so push it onto our list of objects
-            if !models.faces.is_empty() {
-                models.pop_model(load_options)?;
+        "parm" => {
+            let direction = parse_direction(words.next())?;
+            let knots = parse_values(words)?;
+            if !valid_knots(&knots) {
+                return Err(LoadErrorKind::FreeFormParseError);
             }
-            let size = line.chars().next().unwrap().len_utf8();
-            models.name = line[size..].trim().to_owned();
-            if models.name.is_empty() {
-                models.name = "unnamed_object".to_owned();
+            match (models.free_form_body()?, direction) {
+                (FreeFormBodyMut::Curve(curve), 0) => curve.knots = knots,
+                (FreeFormBodyMut::Curve2D(curve), 0) => curve.knots = knots,
+                (FreeFormBodyMut::Surface(surface), direction) => surface.knots[direction] = knots,
+                _ => return Err(LoadErrorKind::FreeFormParseError),
             }
-            Ok(ParseReturnType::None)
         }
-        Some("mtllib") => {
-            // File name can include spaces so we cannot rely on a SplitWhitespace iterator
-            let mtllib = line.split_once(' ').unwrap_or_default().1.trim();
-            let mat_file = Path::new(mtllib).to_path_buf();
-            Ok(ParseReturnType::LoadMaterial(mat_file))
+        "trim" | "hole" | "scrv" => {
+            let segments = parse_curve_segments(words, &models.curves_2d)?;
+            let FreeFormBodyMut::Surface(surface) = models.free_form_body()? else {
+                return Err(LoadErrorKind::FreeFormParseError);
+            };
+            match keyword {
+                "trim" => surface.trims.push(segments),
+                "hole" => surface.holes.push(segments),
+                _ => surface.special_curves.push(segments),
+            }
         }
-        Some("usemtl") => {
-            let mat_name = line.split_once(' ').unwrap_or_default().1.trim().to_owned();
-
-            if !mat_name.is_empty() {
-                let new_mat = materials.mat_map.get(&mat_name).cloned();
-                // As materials are returned per-model, a new material within an object
-                // has to emit a new model with the same name but different material
-                if models.mat_id != new_mat && !models.faces.is_empty() {
-                    models.pop_model(load_options)?;
-                }
-                if new_mat.is_none() {
-                    #[cfg(feature = "log")]
-                    log::warn!(
-                        "Object {} refers to unfound material: {}",
-                        models.name,
-                        mat_name
-                    );
-                }
-                models.mat_id = new_mat;
-                Ok(ParseReturnType::None)
-            } else {
-                Err(LoadError::MaterialParseError)
+        "sp" => {
+            let points = words
+                .map(|word| {
+                    resolve_index(word, models.param_vertices.len())
+                        .map(|vp| models.param_vertices[vp])
+                        .ok_or(LoadErrorKind::FreeFormParseError)
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            match models.free_form_body()? {
+                FreeFormBodyMut::Curve(curve) => curve.special_points.extend(points),
+                FreeFormBodyMut::Curve2D(curve) => curve.special_points.extend(points),
+                FreeFormBodyMut::Surface(surface) => surface.special_points.extend(points),
             }
         }
-        // Just ignore unrecognized characters
-        Some(_) => Ok(ParseReturnType::None),
+        // `end`
+        _ => {
+            models.free_form_body()?;
+            models.free_form_body = None;
+        }
     }
+    Ok(())
 }
 
 #[inline]
 fn parse_mtl_line(
     line: std::io::Result<String>,
     materials: &mut TmpMaterials,
-    mut cur_mat: Material,
+    cur_mat: Material,
 ) -> Result<Material, LoadError> {
-    let (line, mut words) = match line {
-        Ok(ref line) => (line.trim(), line[..].split_whitespace()),
-        Err(_e) => {
+    materials.line += 1;
+    let line = match line {
+        Ok(line) => line,
+        Err(e) => {
             #[cfg(feature = "log")]
-            log::error!("load_obj - failed to read line due to {}", _e);
-            return Err(LoadError::ReadError);
+            log::error!("load_obj - failed to read line due to {}", e);
+            return Err(LoadError::new(LoadErrorKind::ReadError)
+                .with_io_error(e)
+                .at_line(materials.line, None));
         }
     };
+    let line_number = materials.line;
+    parse_mtl_directive(line.trim(), materials, cur_mat)
+        .map_err(|e| e.at_line(line_number, line.split_whitespace().next()))
+}
 
+#[inline]
+fn parse_mtl_directive(
+    line: &str,
+    materials: &mut TmpMaterials,
+    mut cur_mat: Material,
+) -> Result<Material, LoadError> {
+    let mut words = line.split_whitespace();
     match words.next() {
         Some("#") | None => {}
         Some("newmtl") => {
@@ -1813,7 +5774,7 @@ fn parse_mtl_line(
             cur_mat = Material::default();
             cur_mat.name = line[6..].trim().to_owned();
             if cur_mat.name.is_empty() {
-                return Err(LoadError::InvalidObjectName);
+                return Err(LoadErrorKind::InvalidObjectName.into());
             }
         }
         Some("Ka") => cur_mat.ambient = Some(parse_float3(words)?),
@@ -1824,47 +5785,114 @@ fn parse_mtl_line(
         Some("Ni") => cur_mat.optical_density = Some(parse_float(words.next())?),
         Some("d") => cur_mat.dissolve = Some(parse_float(words.next())?),
         Some("map_Ka") => match line.get(6..).map(str::trim) {
-            Some("") | None => return Err(LoadError::MaterialParseError),
-            Some(tex) => cur_mat.ambient_texture = Some(tex.to_owned()),
+            Some("") | None => return Err(LoadErrorKind::MaterialParseError.into()),
+            Some(tex) => {
+                cur_mat.ambient_texture =
+                    Some(materials.texture_map(&cur_mat.name, "map_Ka", tex)?)
+            }
         },
         Some("map_Kd") => match line.get(6..).map(str::trim) {
-            Some("") | None => return Err(LoadError::MaterialParseError),
-            Some(tex) => cur_mat.diffuse_texture = Some(tex.to_owned()),
+            Some("") | None => return Err(LoadErrorKind::MaterialParseError.into()),
+            Some(tex) => {
+                cur_mat.diffuse_texture =
+                    Some(materials.texture_map(&cur_mat.name, "map_Kd", tex)?)
+            }
         },
         Some("map_Ks") => match line.get(6..).map(str::trim) {
-            Some("") | None => return Err(LoadError::MaterialParseError),
-            Some(tex) => cur_mat.specular_texture = Some(tex.to_owned()),
+            Some("") | None => return Err(LoadErrorKind::MaterialParseError.into()),
+            Some(tex) => {
+                cur_mat.specular_texture =
+                    Some(materials.texture_map(&cur_mat.name, "map_Ks", tex)?)
+            }
         },
         Some("map_Bump") | Some("map_bump") => match line.get(8..).map(str::trim) {
-            Some("") | None => return Err(LoadError::MaterialParseError),
-            Some(tex) => cur_mat.normal_texture = Some(tex.to_owned()),
+            Some("") | None => return Err(LoadErrorKind::MaterialParseError.into()),
+            Some(tex) => {
+                cur_mat.normal_texture =
+                    Some(materials.texture_map(&cur_mat.name, "map_Bump", tex)?)
+            }
         },
         Some("map_Ns") | Some("map_ns") | Some("map_NS") => match line.get(6..).map(str::trim) {
-            Some("") | None => return Err(LoadError::MaterialParseError),
-            Some(tex) => cur_mat.shininess_texture = Some(tex.to_owned()),
+            Some("") | None => return Err(LoadErrorKind::MaterialParseError.into()),
+            Some(tex) => {
+                cur_mat.shininess_texture =
+                    Some(materials.texture_map(&cur_mat.name, "map_Ns", tex)?)
+            }
         },
         Some("bump") => match line.get(4..).map(str::trim) {
-            Some("") | None => return Err(LoadError::MaterialParseError),
-            Some(tex) => cur_mat.normal_texture = Some(tex.to_owned()),
+            Some("") | None => return Err(LoadErrorKind::MaterialParseError.into()),
+            Some(tex) => {
+                cur_mat.normal_texture = Some(materials.texture_map(&cur_mat.name, "bump", tex)?)
+            }
         },
         Some("map_d") => match line.get(5..).map(str::trim) {
-            Some("") | None => return Err(LoadError::MaterialParseError),
-            Some(tex) => cur_mat.dissolve_texture = Some(tex.to_owned()),
+            Some("") | None => return Err(LoadErrorKind::MaterialParseError.into()),
+            Some(tex) => {
+                cur_mat.dissolve_texture =
+                    Some(materials.texture_map(&cur_mat.name, "map_d", tex)?)
+            }
+        },
+        Some("Pr") => cur_mat.roughness = Some(parse_float(words.next())?),
+        Some("Pm") => cur_mat.metallic = Some(parse_float(words.next())?),
+        Some("Ps") => cur_mat.sheen = Some(parse_float(words.next())?),
+        Some("Pc") => cur_mat.clearcoat_thickness = Some(parse_float(words.next())?),
+        Some("Pcr") => cur_mat.clearcoat_roughness = Some(parse_float(words.next())?),
+        Some("aniso") => cur_mat.anisotropy = Some(parse_float(words.next())?),
+        Some("anisor") => cur_mat.anisotropy_rotation = Some(parse_float(words.next())?),
+        Some("map_Pr") => match line.get(6..).map(str::trim) {
+            Some("") | None => return Err(LoadErrorKind::MaterialParseError.into()),
+            Some(tex) => {
+                cur_mat.roughness_texture =
+                    Some(materials.texture_map(&cur_mat.name, "map_Pr", tex)?)
+            }
+        },
+        Some("map_Pm") => match line.get(6..).map(str::trim) {
+            Some("") | None => return Err(LoadErrorKind::MaterialParseError.into()),
+            Some(tex) => {
+                cur_mat.metallic_texture =
+                    Some(materials.texture_map(&cur_mat.name, "map_Pm", tex)?)
+            }
+        },
+        Some("map_Ps") => match line.get(6..).map(str::trim) {
+            Some("") | None => return Err(LoadErrorKind::MaterialParseError.into()),
+            Some(tex) => {
+                cur_mat.sheen_texture = Some(materials.texture_map(&cur_mat.name, "map_Ps", tex)?)
+            }
+        },
+        Some("map_Ke") => match line.get(6..).map(str::trim) {
+            Some("") | None => return Err(LoadErrorKind::MaterialParseError.into()),
+            Some(tex) => {
+                cur_mat.emissive_texture =
+                    Some(materials.texture_map(&cur_mat.name, "map_Ke", tex)?)
+            }
+        },
+        Some("norm") => match line.get(4..).map(str::trim) {
+            Some("") | None => return Err(LoadErrorKind::MaterialParseError.into()),
+            Some(tex) => {
+                cur_mat.normal_map_texture =
+                    Some(materials.texture_map(&cur_mat.name, "norm", tex)?)
+            }
         },
         Some("illum") => {
             if let Some(p) = words.next() {
                 match FromStr::from_str(p) {
                     Ok(x) => cur_mat.illumination_model = Some(x),
-                    Err(_) => return Err(LoadError::MaterialParseError),
+                    Err(_) => return Err(LoadErrorKind::MaterialParseError.into()),
                 }
             } else {
-                return Err(LoadError::MaterialParseError);
+                return Err(LoadErrorKind::MaterialParseError.into());
             }
         }
         Some(unknown) => {
             if !unknown.is_empty() {
                 let param = line[unknown.len()..].trim().to_owned();
                 cur_mat.unknown_param.insert(unknown.to_owned(), param);
+                materials
+                    .warnings
+                    .push(LoadWarning::UnknownMaterialParameter {
+                        material: cur_mat.name.clone(),
+                        key: unknown.to_owned(),
+                    });
             }
         }
     }
@@ -1887,10 +5915,12 @@ where
 {
     let file = match File::open(file_name.as_ref()) {
         Ok(f) => f,
-        Err(_e) => {
+        Err(e) => {
             #[cfg(feature = "log")]
-            log::error!("load_obj - failed to open {:?} due to {}", file_name, _e);
-            return Err(LoadError::OpenFileFailed);
+            log::error!("load_obj - failed to open {:?} due to {}", file_name, e);
+            return Err(LoadError::new(LoadErrorKind::OpenFileFailed)
+                .with_io_error(e)
+                .in_file(file_name.as_ref()));
         }
     };
     let mut reader = BufReader::new(file);
@@ -1903,26 +5933,30 @@ where
 
         self::load_mtl(full_path)
     })
+    .map_err(|e| e.in_file(file_name.as_ref()))
 }
 
 /// Load the materials defined in a `MTL` file.
 ///
-/// Returns a pair with a `Vec` holding all loaded materials and a `HashMap`
-/// containing a mapping of material names to indices in the Vec.
+/// Returns a `Vec` holding all loaded materials, a `HashMap` containing a
+/// mapping of material names to indices in the Vec and the warnings found while
+/// loading.
 pub fn load_mtl<P>(file_name: P) -> MTLLoadResult
 where
     P: AsRef<Path> + fmt::Debug,
 {
     let file = match File::open(file_name.as_ref()) {
         Ok(f) => f,
-        Err(_e) => {
+        Err(e) => {
             #[cfg(feature = "log")]
-            log::error!("load_mtl - failed to open {:?} due to {}", file_name, _e);
-            return Err(LoadError::OpenFileFailed);
+            log::error!("load_mtl - failed to open {:?} due to {}", file_name, e);
+            return Err(LoadError::new(LoadErrorKind::OpenFileFailed)
+                .with_io_error(e)
+                .in_file(file_name.as_ref()));
         }
     };
     let mut reader = BufReader::new(file);
-    load_mtl_buf(&mut reader)
+    load_mtl_buf(&mut reader).map_err(|e| e.in_file(file_name.as_ref()))
 }
 
 /// Load the various meshes in an `OBJ` buffer.
@@ -1989,16 +6023,184 @@ pub fn load_obj_buf<B, ML>(
 where
     B: BufRead,
     ML: Fn(&Path) -> MTLLoadResult,
+{
+    let mut models = TmpModels::new();
+    let materials = parse_obj_lines(reader.lines(), load_options, material_loader, &mut models)?;
+    models.into_load_result(materials)
+}
+
+/// Load the various meshes in the bytes of an `OBJ` file, e.g. a file read or
+/// mapped into memory as a whole.
+///
+/// Works like [`load_obj_buf()`], but the lines are not copied into `String`s
+/// and positions, texture coordinates, normals, faces, lines and points are
+/// parsed straight from the bytes. Only the other statements, e.g. names and
+/// materials, are checked to be valid UTF-8.
+///
+/// # Arguments
+///
+/// * `material_loader` – Returns a material given a name, see
+///   [`load_obj_buf()`].
+///
+/// * `load_options` – Governs on-the-fly processing of the mesh during loading.
+///   See [`LoadOptions`] for more information.
+pub fn load_obj_bytes<ML>(
+    bytes: &[u8],
+    load_options: &LoadOptions,
+    material_loader: ML,
+) -> LoadResult
+where
+    ML: Fn(&Path) -> MTLLoadResult,
+{
+    let mut models = TmpModels::new();
+    let materials = parse_obj_lines(
+        byte_lines(bytes),
+        load_options,
+        material_loader,
+        &mut models,
+    )?;
+    models.into_load_result(materials)
+}
+
+/// Load the various objects specified in the `OBJ` file and any associated
+/// `MTL` file like [`load_obj()`], but with the `OBJ` file mapped into memory
+/// and parsed by [`load_obj_bytes()`].
+///
+/// Only present when the `mmap` feature is enabled.
+///
+/// # Safety
+///
+/// The file must not be modified while it is loaded, e.g. by another process.
+/// See [`memmap2::Mmap`].
+#[cfg(feature = "mmap")]
+pub unsafe fn load_obj_mmap<P>(file_name: P, load_options: &LoadOptions) -> LoadResult
+where
+    P: AsRef<Path> + fmt::Debug,
+{
+    let file = match File::open(file_name.as_ref()) {
+        Ok(f) => f,
+        Err(e) => {
+            #[cfg(feature = "log")]
+            log::error!(
+                "load_obj_mmap - failed to open {:?} due to {}",
+                file_name,
+                e
+            );
+            return Err(LoadError::new(LoadErrorKind::OpenFileFailed)
+                .with_io_error(e)
+                .in_file(file_name.as_ref()));
+        }
+    };
+    let mmap = match memmap2::Mmap::map(&file) {
+        Ok(mmap) => mmap,
+        Err(e) => {
+            #[cfg(feature = "log")]
+            log::error!("load_obj_mmap - failed to map {:?} due to {}", file_name, e);
+            return Err(LoadError::new(LoadErrorKind::ReadError)
+                .with_io_error(e)
+                .in_file(file_name.as_ref()));
+        }
+    };
+    load_obj_bytes(&mmap, load_options, |mat_path| {
+        let full_path = if let Some(parent) = file_name.as_ref().parent() {
+            parent.join(mat_path)
+        } else {
+            mat_path.to_owned()
+        };
+
+        self::load_mtl(full_path)
+    })
+    .map_err(|e| e.in_file(file_name.as_ref()))
+}
+
+/// Split `bytes` into lines like [`BufRead::lines()`] does.
+fn byte_lines(bytes: &[u8]) -> impl Iterator<Item = std::io::Result<&[u8]>> {
+    // A line break at the end doesn't start another line
+    let bytes = bytes.strip_suffix(b"\n").unwrap_or(bytes);
+    bytes
+        .split(|&byte| byte == b'\n')
+        .take(if bytes.is_empty() { 0 } else { usize::MAX })
+        .map(|line| Ok(line.strip_suffix(b"\r").unwrap_or(line)))
+}
+
+/// Load the geometry of an `OBJ` file as it was specified.
+///
+/// Returns the vertex data of the whole file, shared by the faces of all
+/// shapes, instead of a separate [`Mesh`] for each [`Model`]. See [`RawObj`].
+///
+/// # Arguments
+///
+/// * `load_options` – Only the options that govern parsing apply, i.e.
+///   [`per_face_materials`](LoadOptions::per_face_materials) and
+///   [`skip_invalid_lines`](LoadOptions::skip_invalid_lines). The faces are not
+///   processed in any way.
+pub fn load_obj_raw<P>(file_name: P, load_options: &LoadOptions) -> RawLoadResult
+where
+    P: AsRef<Path> + fmt::Debug,
+{
+    let file = match File::open(file_name.as_ref()) {
+        Ok(f) => f,
+        Err(e) => {
+            #[cfg(feature = "log")]
+            log::error!("load_obj_raw - failed to open {:?} due to {}", file_name, e);
+            return Err(LoadError::new(LoadErrorKind::OpenFileFailed)
+                .with_io_error(e)
+                .in_file(file_name.as_ref()));
+        }
+    };
+    let mut reader = BufReader::new(file);
+    load_obj_buf_raw(&mut reader, load_options, |mat_path| {
+        let full_path = if let Some(parent) = file_name.as_ref().parent() {
+            parent.join(mat_path)
+        } else {
+            mat_path.to_owned()
+        };
+
+        self::load_mtl(full_path)
+    })
+    .map_err(|e| e.in_file(file_name.as_ref()))
+}
+
+/// Load the geometry of an `OBJ` buffer as it was specified.
+///
+/// This works like [`load_obj_buf()`] but returns the geometry like
+/// [`load_obj_raw()`].
+pub fn load_obj_buf_raw<B, ML>(
+    reader: &mut B,
+    load_options: &LoadOptions,
+    material_loader: ML,
+) -> RawLoadResult
+where
+    B: BufRead,
+    ML: Fn(&Path) -> MTLLoadResult,
+{
+    let mut models = TmpModels {
+        raw_shapes: Some(Vec::new()),
+        ..TmpModels::new()
+    };
+    let materials = parse_obj_lines(reader.lines(), load_options, material_loader, &mut models)?;
+    models.into_raw_load_result(materials, load_options)
+}
+
+/// Parse an `OBJ` buffer into `models`, returning the materials loaded.
+fn parse_obj_lines<L, ML>(
+    lines: impl Iterator<Item = std::io::Result<L>>,
+    load_options: &LoadOptions,
+    material_loader: ML,
+    models: &mut TmpModels,
+) -> Result<TmpMaterials, LoadError>
+where
+    L: AsRef<[u8]>,
+    ML: Fn(&Path) -> MTLLoadResult,
 {
     if !load_options.is_valid() {
-        return Err(LoadError::InvalidLoadOptionConfig);
+        return Err(LoadErrorKind::InvalidLoadOptionConfig.into());
     }
 
-    let mut models = TmpModels::new();
     let mut materials = TmpMaterials::new();
 
-    for line in reader.lines() {
-        let parse_return = parse_obj_line(line, load_options, &mut models, &materials)?;
+    for line in lines {
+        let parse_return = parse_obj_line(line, load_options, models, &materials)?;
         match parse_return {
             ParseReturnType::LoadMaterial(mat_file) => {
                 materials.merge(material_loader(mat_file.as_path()));
@@ -2010,9 +6212,9 @@ where
     // For the last object in the file we won't encounter another object name to
     // tell us when it's done, so if we're parsing an object push the last one
     // on the list as well
-    models.pop_model(load_options)?;
+    models.finish(load_options)?;
 
-    Ok((models.into_models(), materials.into_materials()))
+    Ok(materials)
 }
 
 /// Load the various materials in a `MTL` buffer.
@@ -2033,6 +6235,340 @@ pub fn load_mtl_buf<B: BufRead>(reader: &mut B) -> MTLLoadResult {
     materials.into_mtl_load_result()
 }
 
+/// Save the models to an `OBJ` file.
+///
+/// See [`write_obj_buf()`] for the arguments and how the models are written.
+pub fn save_obj<P>(
+    file_name: P,
+    models: &[Model],
+    materials: &[Material],
+    mtllib: Option<&str>,
+) -> std::io::Result<()>
+where
+    P: AsRef<Path> + fmt::Debug,
+{
+    let file = match File::create(file_name.as_ref()) {
+        Ok(f) => f,
+        Err(e) => {
+            #[cfg(feature = "log")]
+            log::error!("save_obj - failed to create {:?} due to {}", file_name, e);
+            return Err(e);
+        }
+    };
+    let mut writer = BufWriter::new(file);
+    write_obj_buf(&mut writer, models, materials, mtllib)?;
+    writer.flush()
+}
+
+/// Write the models to an `OBJ` buffer.
+///
+/// Each model is written with its own positions, texture coordinates, normals
+/// and vertex colors, followed by its faces. Positions are written with their
+/// [`position_w`](Mesh::position_w) unless the mesh has vertex colors, which
+/// can't be told apart from it in the file. An `o` statement is written when
+/// the [`object`](Model::object) of a model differs from the model before it,
+/// and a `g` statement when its [`groups`](Model::groups) do. As objects reset
+/// the groups, models in no group following models in one repeat the `o`
+/// statement instead. Models that are in no object or group are written as an
+/// object with their name instead.
+/// Models that continue the object and groups (or name) of the model before
+/// them (e.g. because they were split on a `usemtl` statement during loading)
+/// continue the same object.
+///
+/// Like when loading, the material and smoothing group carry over from one
+/// model to the next, so `usemtl` and `s` statements are only written when
+/// they change. Faces without a material following faces with one get a
+/// `usemtl (null)` statement, which loads as no material, faces without a
+/// smoothing group following faces in one get `s off`.
+///
+/// Both meshes loaded with [`single_index`](LoadOptions::single_index) and
+/// meshes with separate [`texcoord_indices`](Mesh::texcoord_indices) and
+/// [`normal_indices`](Mesh::normal_indices) are supported, as are meshes with
+/// any [`face_arities`](Mesh::face_arities).
+///
+/// # Arguments
+///
+/// * `materials` – The materials the [`material_id`](Mesh::material_id)s and
+///   [`face_material_ids`](Mesh::face_material_ids) of the meshes refer to.
+///   Used to name the material in the `usemtl` statements. Material ids which
+///   are not found get the name `material_<id>`.
+///
+/// * `mtllib` – Name of the material library to reference via a `mtllib`
+///   statement, if any.
+pub fn write_obj_buf<W: Write>(
+    writer: &mut W,
+    models: &[Model],
+    materials: &[Material],
+    mtllib: Option<&str>,
+) -> std::io::Result<()> {
+    if let Some(mtllib) = mtllib {
+        writeln!(writer, "mtllib {}", mtllib)?;
+    }
+
+    // OBJ indices are global to the file, so each model's indices are offset
+    // by the number of elements written before it.
+    let mut pos_offset = 1;
+    let mut texcoord_offset = 1;
+    let mut normal_offset = 1;
+    let mut name = None;
+    let mut object = None;
+    let mut groups: &[String] = &[];
+    let mut material_id = None;
+    let mut smoothing_group = None;
+
+    for model in models {
+        let mesh = &model.mesh;
+
+        if model.object.is_none() && model.groups.is_empty() {
+            if name != Some(&model.name) {
+                writeln!(writer, "o {}", model.name)?;
+                (object, groups) = (Some(&model.name), &[]);
+            }
+        } else {
+            // Objects reset the groups, which is also the only way to leave
+            // all groups without starting an unnamed model
+            if object != model.object.as_ref() || model.groups.is_empty() && !groups.is_empty() {
+                if let Some(model_object) = &model.object {
+                    writeln!(writer, "o {}", model_object)?;
+                }
+                (object, groups) = (model.object.as_ref(), &[]);
+            }
+            if groups != model.groups {
+                writeln!(writer, "g {}", model.groups.join(" "))?;
+                groups = &model.groups;
+            }
+        }
+        name = Some(&model.name);
+        if mesh.face_material_ids.is_empty() {
+            write_usemtl(writer, materials, &mut material_id, mesh.material_id)?;
+        }
+
+        let vertex_count = mesh.positions.len() / 3;
+        let has_vertex_color = mesh.vertex_color.len() == mesh.positions.len();
+        for v in 0..vertex_count {
+            let p = &mesh.positions[v * 3..v * 3 + 3];
+            write!(writer, "v {} {} {}", p[0], p[1], p[2])?;
+            if has_vertex_color {
+                let c = &mesh.vertex_color[v * 3..v * 3 + 3];
+                write!(writer, " {} {} {}", c[0], c[1], c[2])?;
+                if let Some(alpha) = mesh.vertex_color_alpha.get(v) {
+                    write!(writer, " {}", alpha)?;
+                }
+            } else if let Some(w) = mesh.position_w.get(v) {
+                write!(writer, " {}", w)?;
+            }
+            writeln!(writer)?;
+        }
+        for (i, vt) in mesh.texcoords.chunks_exact(2).enumerate() {
+            write!(writer, "vt {} {}", vt[0], vt[1])?;
+            if let Some(w) = mesh.texcoord_w.get(i) {
+                write!(writer, " {}", w)?;
+            }
+            writeln!(writer)?;
+        }
+        for vn in mesh.normals.chunks_exact(3) {
+            writeln!(writer, "vn {} {} {}", vn[0], vn[1], vn[2])?;
+        }
+
+        // Texture coordinates and normals either have their own indices, share
+        // the vertex indices or, if reordered per-vertex-per-face, are in order.
+        let attribute_index = |indices: &[u32], count: usize, corner: usize| {
+            if !indices.is_empty() {
+                indices[corner] as usize
+            } else if count == vertex_count {
+                mesh.indices[corner] as usize
+            } else {
+                corner
+            }
+        };
+        let texcoord_count = mesh.texcoords.len() / 2;
+        let normal_count = mesh.normals.len() / 3;
+
+        for (f, face) in mesh.face_ranges().into_iter().enumerate() {
+            if let Some(&face_material_id) = mesh.face_material_ids.get(f) {
+                write_usemtl(writer, materials, &mut material_id, face_material_id)?;
+            }
+            // Meshes without smoothing groups are in none, which only has to
+            // be written if the faces before are in one
+            let group = mesh.smoothing_groups.get(f).copied();
+            if let Some(group) = group.or(smoothing_group.map(|_| 0)) {
+                if smoothing_group != Some(group) {
+                    match group {
+                        0 => writeln!(writer, "s off")?,
+                        group => writeln!(writer, "s {}", group)?,
+                    }
+                    smoothing_group = Some(group);
+                }
+            }
+
+            write!(writer, "f")?;
+            for corner in face {
+                write!(writer, " {}", mesh.indices[corner] as usize + pos_offset)?;
+                let vt = (texcoord_count != 0).then(|| {
+                    attribute_index(&mesh.texcoord_indices, texcoord_count, corner)
+                        + texcoord_offset
+                });
+                let vn = (normal_count != 0).then(|| {
+                    attribute_index(&mesh.normal_indices, normal_count, corner) + normal_offset
+                });
+                match (vt, vn) {
+                    (None, None) => {}
+                    (Some(vt), None) => write!(writer, "/{}", vt)?,
+                    (None, Some(vn)) => write!(writer, "//{}", vn)?,
+                    (Some(vt), Some(vn)) => write!(writer, "/{}/{}", vt, vn)?,
+                }
+            }
+            writeln!(writer)?;
+        }
+
+        for &point in &mesh.point_indices {
+            writeln!(writer, "p {}", point as usize + pos_offset)?;
+        }
+
+        let mut corner = 0;
+        for &arity in &mesh.line_arities {
+            write!(writer, "l")?;
+            for _ in 0..arity {
+                write!(
+                    writer,
+                    " {}",
+                    mesh.line_indices[corner] as usize + pos_offset
+                )?;
+                if let Some(&vt) = mesh.line_texcoord_indices.get(corner) {
+                    write!(writer, "/{}", vt as usize + texcoord_offset)?;
+                } else if texcoord_count != 0 && texcoord_count == vertex_count {
+                    write!(
+                        writer,
+                        "/{}",
+                        mesh.line_indices[corner] as usize + texcoord_offset
+                    )?;
+                }
+                corner += 1;
+            }
+            writeln!(writer)?;
+        }
+
+        pos_offset += vertex_count;
+        texcoord_offset += texcoord_count;
+        normal_offset += normal_count;
+    }
+
+    Ok(())
+}
+
+/// Write a `usemtl` statement if the material `id` differs from the `current`
+/// one, which is updated to it.
+fn write_usemtl<W: Write>(
+    writer: &mut W,
+    materials: &[Material],
+    current: &mut Option<usize>,
+    id: Option<usize>,
+) -> std::io::Result<()> {
+    if *current == id {
+        return Ok(());
+    }
+    match id.map(|id| (id, materials.get(id))) {
+        Some((_, Some(material))) => writeln!(writer, "usemtl {}", material.name)?,
+        Some((id, None)) => writeln!(writer, "usemtl material_{}", id)?,
+        // There is no statement for no material, the loader takes this name
+        // as none
+        None => writeln!(writer, "usemtl {}", NO_MATERIAL)?,
+    }
+    *current = id;
+    Ok(())
+}
+
+/// Save the materials to a `MTL` file.
+///
+/// See [`write_mtl_buf()`] for how the materials are written.
+pub fn save_mtl<P>(file_name: P, materials: &[Material]) -> std::io::Result<()>
+where
+    P: AsRef<Path> + fmt::Debug,
+{
+    let file = match File::create(file_name.as_ref()) {
+        Ok(f) => f,
+        Err(e) => {
+            #[cfg(feature = "log")]
+            log::error!("save_mtl - failed to create {:?} due to {}", file_name, e);
+            return Err(e);
+        }
+    };
+    let mut writer = BufWriter::new(file);
+    write_mtl_buf(&mut writer, materials)?;
+    writer.flush()
+}
+
+/// Write the materials to a `MTL` buffer.
+///
+/// All attributes of a [`Material`] are written, including its
+/// [`unknown_param`](Material::unknown_param)s, so loading the written
+/// materials with [`load_mtl_buf()`] gives the same materials again.
+///
+/// Texture maps are written from their options and path, their
+/// [`raw`](TextureMap::raw) statement is ignored. So the written statement may
+/// differ from the loaded one, e.g. in the order of the options, and options
+/// with invalid values that were skipped when loading are not written.
+pub fn write_mtl_buf<W: Write>(writer: &mut W, materials: &[Material]) -> std::io::Result<()> {
+    for (i, material) in materials.iter().enumerate() {
+        if i > 0 {
+            writeln!(writer)?;
+        }
+        writeln!(writer, "newmtl {}", material.name)?;
+
+        let colors = [
+            ("Ka", material.ambient),
+            ("Kd", material.diffuse),
+            ("Ks", material.specular),
+            ("Ke", material.emissive),
+        ];
+        for (keyword, color) in colors {
+            if let Some(c) = color {
+                writeln!(writer, "{} {} {} {}", keyword, c[0], c[1], c[2])?;
+            }
+        }
+
+        let scalars = [
+            ("Ns", material.shininess),
+            ("Ni", material.optical_density),
+            ("d", material.dissolve),
+            ("Pr", material.roughness),
+            ("Pm", material.metallic),
+            ("Ps", material.sheen),
+            ("Pc", material.clearcoat_thickness),
+            ("Pcr", material.clearcoat_roughness),
+            ("aniso", material.anisotropy),
+            ("anisor", material.anisotropy_rotation),
+        ];
+        for (keyword, value) in scalars {
+            if let Some(value) = value {
+                writeln!(writer, "{} {}", keyword, value)?;
+            }
+        }
+        if let Some(illumination_model) = material.illumination_model {
+            writeln!(writer, "illum {}", illumination_model)?;
+        }
+
+        for (keyword, texture) in material.texture_maps() {
+            if let Some(texture) = texture {
+                writeln!(writer, "{} {}", keyword, texture)?;
+            }
+        }
+
+        // Sorted so the output does not depend on the order of the map.
+        let mut unknown_params = material.unknown_param.iter().collect::<Vec<_>>();
+        unknown_params.sort();
+        for (keyword, value) in unknown_params {
+            if value.is_empty() {
+                writeln!(writer, "{}", keyword)?;
+            } else {
+                writeln!(writer, "{} {}", keyword, value)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
 #[cfg(feature = "async")]
 /// Load the various meshes in an `OBJ` buffer.
 ///
@@ -2121,7 +6657,7 @@ where
     MLFut: Future<Output = MTLLoadResult>,
 {
     if !load_options.is_valid() {
-        return Err(LoadError::InvalidLoadOptionConfig);
+        return Err(LoadErrorKind::InvalidLoadOptionConfig.into());
     }
 
     let mut models = TmpModels::new();
@@ -2138,7 +6674,7 @@ where
                         log::error!(
                             "load_obj - material path contains invalid Unicode: {_mat_file:?}"
                         );
-                        return Err(LoadError::ReadError);
+                        return Err(LoadErrorKind::ReadError.into());
                     }
                 }
             }
@@ -2149,9 +6685,9 @@ where
     // For the last object in the file we won't encounter another object name to
     // tell us when it's done, so if we're parsing an object push the last one
     // on the list as well
-    models.pop_model(load_options)?;
+    models.finish(load_options)?;
 
-    Ok((models.into_models(), materials.into_materials()))
+    models.into_load_result(materials)
 }
 
 /// Optional module supporting async loading with `futures` traits.
@@ -2222,7 +6758,7 @@ pub mod futures {
         MLFut: Future<Output = MTLLoadResult>,
     {
         if !load_options.is_valid() {
-            return Err(LoadError::InvalidLoadOptionConfig);
+            return Err(LoadErrorKind::InvalidLoadOptionConfig.into());
         }
 
         let mut models = TmpModels::new();
@@ -2243,9 +6779,9 @@ pub mod futures {
         // For the last object in the file we won't encounter another object name to
         // tell us when it's done, so if we're parsing an object push the last one
         // on the list as well
-        models.pop_model(load_options)?;
+        models.finish(load_options)?;
 
-        Ok((models.into_models(), materials.into_materials()))
+        models.into_load_result(materials)
     }
 
     /// Asynchronously load the various materials in a `MTL` buffer.
@@ -2301,10 +6837,12 @@ pub mod tokio {
     {
         let file = match File::open(file_name.as_ref()).await {
             Ok(f) => f,
-            Err(_e) => {
+            Err(e) => {
                 #[cfg(feature = "log")]
-                log::error!("load_obj - failed to open {:?} due to {}", file_name, _e);
-                return Err(LoadError::OpenFileFailed);
+                log::error!("load_obj - failed to open {:?} due to {}", file_name, e);
+                return Err(LoadError::new(LoadErrorKind::OpenFileFailed)
+                    .with_io_error(e)
+                    .in_file(file_name.as_ref()));
             }
         };
         load_obj_buf(BufReader::new(file), load_options, |mat_path| {
@@ -2323,6 +6861,7 @@ pub mod tokio {
             }
         })
         .await
+        .map_err(|e| e.in_file(file_name.as_ref()))
     }
 
     /// Load the materials defined in a `MTL` file.
@@ -2338,13 +6877,17 @@ pub mod tokio {
     {
         let file = match File::open(file_name.as_ref()).await {
             Ok(f) => f,
-            Err(_e) => {
+            Err(e) => {
                 #[cfg(feature = "log")]
-                log::error!("load_mtl - failed to open {:?} due to {}", file_name, _e);
-                return Err(LoadError::OpenFileFailed);
+                log::error!("load_mtl - failed to open {:?} due to {}", file_name, e);
+                return Err(LoadError::new(LoadErrorKind::OpenFileFailed)
+                    .with_io_error(e)
+                    .in_file(file_name.as_ref()));
             }
         };
-        load_mtl_buf(BufReader::new(file)).await
+        load_mtl_buf(BufReader::new(file))
+            .await
+            .map_err(|e| e.in_file(file_name.as_ref()))
     }
 
     /// Asynchronously load the various meshes in an 'OBJ' buffer.
@@ -2366,7 +6909,7 @@ pub mod tokio {
         MLFut: Future<Output = MTLLoadResult>,
     {
         if !load_options.is_valid() {
-            return Err(LoadError::InvalidLoadOptionConfig);
+            return Err(LoadErrorKind::InvalidLoadOptionConfig.into());
         }
 
         let mut models = TmpModels::new();
@@ -2387,9 +6930,9 @@ pub mod tokio {
         // For the last object in the file we won't encounter another object name to
         // tell us when it's done, so if we're parsing an object push the last one
         // on the list as well
-        models.pop_model(load_options)?;
+        models.finish(load_options)?;
 
-        Ok((models.into_models(), materials.into_materials()))
+        models.into_load_result(materials)
     }
 
     /// Asynchronously load the various materials in a `MTL` buffer.
@@ -25,7 +25,7 @@ fn simple_triangle() {
         },
     );
     assert!(m.is_ok());
-    let (models, mats) = m.unwrap();
+    let (models, mats, _) = m.unwrap();
     let mats = mats.unwrap();
     // We expect a single model with no materials
     assert_eq!(models.len(), 1);
@@ -58,7 +58,7 @@ fn simple_triangle_colored() {
         },
     );
     assert!(m.is_ok());
-    let (models, mats) = m.unwrap();
+    let (models, mats, _) = m.unwrap();
     let mats = mats.unwrap();
     // We expect a single model with no materials
     assert_eq!(models.len(), 1);
@@ -93,7 +93,7 @@ fn simple_quad_colored_merge() {
         },
     );
     assert!(m.is_ok());
-    let (models, mats) = m.unwrap();
+    let (models, mats, _) = m.unwrap();
     let mats = mats.unwrap();
     // We expect a single model with no materials
     assert_eq!(models.len(), 1);
@@ -143,7 +143,7 @@ fn empty_name_triangle() {
         },
     );
     assert!(m.is_ok());
-    let (models, mats) = m.unwrap();
+    let (models, mats, _) = m.unwrap();
     let mats = mats.unwrap();
     // We expect a single model with no materials
     assert_eq!(models.len(), 1);
@@ -173,7 +173,7 @@ fn test_lines() {
         },
     );
     assert!(m.is_ok());
-    let (models, mats) = m.unwrap();
+    let (models, mats, _) = m.unwrap();
     let mats = mats.unwrap();
     // We expect a single model with no materials
     assert_eq!(models.len(), 1);
@@ -188,9 +188,11 @@ fn test_lines() {
     // Verify each position is loaded properly
     let expect_pos = vec![0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0];
     assert_float_eq!(mesh.positions, expect_pos, r2nd_all <= TOL);
-    // Verify the indices are loaded properly
+    // Verify the lines are loaded as polylines, not faces
+    assert!(mesh.indices.is_empty());
     let expect_idx = vec![0, 1, 1, 2, 2, 0];
-    assert_eq!(mesh.indices, expect_idx);
+    assert_eq!(mesh.line_indices, expect_idx);
+    assert_eq!(mesh.line_arities, vec![2, 2, 2]);
 }
 
 #[test]
@@ -203,7 +205,7 @@ fn non_triangulated_quad() {
         },
     );
     assert!(m.is_ok());
-    let (models, mats) = m.unwrap();
+    let (models, mats, _) = m.unwrap();
     let mats = mats.unwrap();
     assert_eq!(models.len(), 3);
     assert!(mats.is_empty());
@@ -233,7 +235,7 @@ fn multiple_face_formats() {
         },
     );
     assert!(m.is_ok());
-    let (models, mats) = m.unwrap();
+    let (models, mats, _) = m.unwrap();
     let mats = mats.unwrap();
     assert_eq!(models.len(), 3);
     assert!(mats.is_empty());
@@ -404,28 +406,28 @@ fn validate_cornell(models: Vec<tobj::Model>, mats: Vec<tobj::Material>) {
     assert_float_eq!(mat.specular.unwrap(), [0.0, 0.0, 0.0], r2nd_all <= TOL);
     assert_eq!(mat.illumination_model, Some(2));
     assert_eq!(
-        mat.ambient_texture,
-        Some("this ambient texture has spaces.jpg".to_owned())
+        mat.ambient_texture.as_ref().map(|t| t.path.as_str()),
+        Some("this ambient texture has spaces.jpg")
     );
     assert_eq!(
-        mat.diffuse_texture,
-        Some("this diffuse texture has spaces.jpg".to_owned())
+        mat.diffuse_texture.as_ref().map(|t| t.path.as_str()),
+        Some("this diffuse texture has spaces.jpg")
     );
     assert_eq!(
-        mat.specular_texture,
-        Some("this specular texture has spaces.jpg".to_owned())
+        mat.specular_texture.as_ref().map(|t| t.path.as_str()),
+        Some("this specular texture has spaces.jpg")
     );
     assert_eq!(
-        mat.normal_texture,
-        Some("this normal texture has spaces.jpg".to_owned())
+        mat.normal_texture.as_ref().map(|t| t.path.as_str()),
+        Some("this normal texture has spaces.jpg")
     );
     assert_eq!(
-        mat.shininess_texture,
-        Some("this shininess texture has spaces.jpg".to_owned())
+        mat.shininess_texture.as_ref().map(|t| t.path.as_str()),
+        Some("this shininess texture has spaces.jpg")
     );
     assert_eq!(
-        mat.dissolve_texture,
-        Some("this dissolve texture has spaces.jpg".to_owned())
+        mat.dissolve_texture.as_ref().map(|t| t.path.as_str()),
+        Some("this dissolve texture has spaces.jpg")
     );
 
     // Verify blue material loaded properly
@@ -456,12 +458,30 @@ fn validate_cornell(models: Vec<tobj::Model>, mats: Vec<tobj::Material>) {
     assert_float_eq!(mat.ambient.unwrap(), [0.0, 0.0, 0.0], r2nd_all <= TOL);
     assert_float_eq!(mat.diffuse.unwrap(), [0.0, 1.0, 0.0], r2nd_all <= TOL);
     assert_float_eq!(mat.specular.unwrap(), [0.0, 0.0, 0.0], r2nd_all <= TOL);
-    assert_eq!(mat.ambient_texture, Some("dummy_texture.png".to_owned()));
-    assert_eq!(mat.diffuse_texture, Some("dummy_texture.png".to_owned()));
-    assert_eq!(mat.specular_texture, Some("dummy_texture.png".to_owned()));
-    assert_eq!(mat.normal_texture, Some("dummy_texture.png".to_owned()));
-    assert_eq!(mat.shininess_texture, Some("dummy_texture.png".to_owned()));
-    assert_eq!(mat.dissolve_texture, Some("dummy_texture.png".to_owned()));
+    assert_eq!(
+        mat.ambient_texture.as_ref().map(|t| t.path.as_str()),
+        Some("dummy_texture.png")
+    );
+    assert_eq!(
+        mat.diffuse_texture.as_ref().map(|t| t.path.as_str()),
+        Some("dummy_texture.png")
+    );
+    assert_eq!(
+        mat.specular_texture.as_ref().map(|t| t.path.as_str()),
+        Some("dummy_texture.png")
+    );
+    assert_eq!(
+        mat.normal_texture.as_ref().map(|t| t.path.as_str()),
+        Some("dummy_texture.png")
+    );
+    assert_eq!(
+        mat.shininess_texture.as_ref().map(|t| t.path.as_str()),
+        Some("dummy_texture.png")
+    );
+    assert_eq!(
+        mat.dissolve_texture.as_ref().map(|t| t.path.as_str()),
+        Some("dummy_texture.png")
+    );
 }
 
 #[test]
@@ -475,7 +495,7 @@ fn test_cornell() {
         },
     );
     assert!(m.is_ok());
-    let (models, mats) = m.unwrap();
+    let (models, mats, _) = m.unwrap();
     let mats = mats.unwrap();
     assert_eq!(models.len(), 8);
     assert_eq!(mats.len(), 5);
@@ -498,7 +518,7 @@ fn test_custom_material_loader() {
         },
     );
     assert!(m.is_ok());
-    let (models, mats) = m.unwrap();
+    let (models, mats, _) = m.unwrap();
     let mats = mats.unwrap();
     assert_eq!(models.len(), 8);
     assert_eq!(mats.len(), 5);
@@ -525,7 +545,7 @@ fn test_async_custom_material_loader() {
         },
     ));
     assert!(m.is_ok());
-    let (models, mats) = m.unwrap();
+    let (models, mats, _) = m.unwrap();
     let mats = mats.unwrap();
     assert_eq!(models.len(), 8);
     assert_eq!(mats.len(), 5);
@@ -560,7 +580,7 @@ mod futures {
             },
         ));
         assert!(m.is_ok());
-        let (models, mats) = m.unwrap();
+        let (models, mats, _) = m.unwrap();
         let mats = mats.unwrap();
         assert_eq!(models.len(), 8);
         assert_eq!(mats.len(), 5);
@@ -601,7 +621,7 @@ fn test_custom_material_loader_files() {
         },
     );
     assert!(m.is_ok());
-    let (models, mats) = m.unwrap();
+    let (models, mats, _) = m.unwrap();
     let mats = mats.unwrap();
     assert_eq!(models.len(), 8);
     assert_eq!(mats.len(), 5);
@@ -620,7 +640,7 @@ fn test_invalid_index() {
     );
     assert!(m.is_err());
     let err = m.err().unwrap();
-    assert_eq!(err, tobj::LoadError::FaceVertexOutOfBounds);
+    assert_eq!(err, tobj::LoadErrorKind::FaceVertexOutOfBounds);
 }
 
 #[cfg(feature = "tokio")]
@@ -640,7 +660,7 @@ mod tokio {
             },
         ));
         assert!(m.is_ok());
-        let (models, mats) = m.unwrap();
+        let (models, mats, _) = m.unwrap();
         let mats = mats.unwrap();
         assert_eq!(models.len(), 8);
         assert_eq!(mats.len(), 5);
@@ -669,10 +689,1824 @@ mod tokio {
             },
         ));
         assert!(m.is_ok());
-        let (models, mats) = m.unwrap();
+        let (models, mats, _) = m.unwrap();
         let mats = mats.unwrap();
         assert_eq!(models.len(), 8);
         assert_eq!(mats.len(), 5);
         validate_cornell(models, mats);
     }
 }
+
+#[test]
+fn test_smoothing_groups() {
+    let m = tobj::load_obj(
+        "obj/smoothing_groups.obj",
+        &tobj::LoadOptions {
+            triangulate: true,
+            single_index: true,
+            ..Default::default()
+        },
+    );
+    assert!(m.is_ok());
+    let (models, _, _) = m.unwrap();
+    assert_eq!(models.len(), 1);
+    // The quad and the pentagon are split into two and three triangles resp.
+    let mesh = &models[0].mesh;
+    assert_eq!(mesh.indices.len() / 3, 6);
+    assert_eq!(mesh.smoothing_groups, vec![1, 1, 0, 0, 0, 2]);
+
+    let (models, _, _) = tobj::load_obj("obj/smoothing_groups.obj", &tobj::LoadOptions::default())
+        .expect("Failed to load OBJ file");
+    let mesh = &models[0].mesh;
+    assert_eq!(mesh.face_arities, vec![4, 5, 3]);
+    assert_eq!(mesh.smoothing_groups, vec![1, 0, 2]);
+
+    // Meshes without any smoothing groups have none
+    let (models, _, _) = tobj::load_obj("obj/quad.obj", &tobj::LoadOptions::default())
+        .expect("Failed to load OBJ file");
+    assert!(models[0].mesh.smoothing_groups.is_empty());
+
+    // Faces explicitly outside of any smoothing group are kept as such, and
+    // get flat normals
+    let obj = "v 0 0 0\nv 1 0 0\nv 0 1 0\nv 0 0 1\ns off\nf 1 2 3\nf 1 4 2\n";
+    let (models, _, _) = tobj::load_obj_buf(
+        &mut Cursor::new(obj),
+        &tobj::LoadOptions {
+            generate_normals: tobj::NormalGeneration::Smooth {
+                weighting: tobj::NormalWeighting::Area,
+                crease_angle: None,
+            },
+            ..Default::default()
+        },
+        |_| unreachable!(),
+    )
+    .expect("Failed to load OBJ buffer");
+    let mesh = &models[0].mesh;
+    assert_eq!(mesh.smoothing_groups, vec![0, 0]);
+    // The shared edge isn't smoothed, so its vertices have a normal per face
+    assert_eq!(mesh.normals.len() / 3, 6);
+
+    // Missing or invalid smoothing groups are taken as `off` with a warning
+    let obj = "v 0 0 0\nv 1 0 0\nv 0 1 0\ns 1\nf 1 2 3\ns\nf 1 2 3\ns 2\nf 1 2 3\ns foo\nf 1 2 3\n";
+    let (models, _, warnings) = tobj::load_obj_buf(
+        &mut Cursor::new(obj),
+        &tobj::LoadOptions::default(),
+        |_| unreachable!(),
+    )
+    .expect("Failed to load OBJ buffer");
+    assert_eq!(models[0].mesh.smoothing_groups, vec![1, 0, 2, 0]);
+    assert_eq!(
+        warnings,
+        vec![
+            tobj::LoadWarning::InvalidSmoothingGroup { line: 6 },
+            tobj::LoadWarning::InvalidSmoothingGroup { line: 10 },
+        ]
+    );
+}
+
+#[test]
+fn test_generate_normals() {
+    // Two faces of a cube sharing an edge, one smoothing group each.
+    let obj = "
+v 0 0 0
+v 1 0 0
+v 1 1 0
+v 0 1 0
+v 1 0 -1
+v 1 1 -1
+s 1
+f 1 2 3 4
+s 2
+f 2 5 6 3
+";
+    let load = |load_options: &tobj::LoadOptions| {
+        let (models, _, _) =
+            tobj::load_obj_buf(&mut Cursor::new(obj), load_options, |_| unreachable!())
+                .expect("Failed to load OBJ buffer");
+        models.into_iter().next().unwrap().mesh
+    };
+
+    let smooth = tobj::NormalGeneration::Smooth {
+        weighting: tobj::NormalWeighting::Angle,
+        crease_angle: None,
+    };
+
+    // The two vertices on the shared edge are split as they have one normal per
+    // smoothing group.
+    let mesh = load(&tobj::LoadOptions {
+        single_index: true,
+        generate_normals: smooth,
+        ..Default::default()
+    });
+    assert_eq!(mesh.positions.len() / 3, 8);
+    assert_eq!(mesh.normals.len(), mesh.positions.len());
+    assert_eq!(mesh.indices, vec![0, 1, 2, 3, 6, 4, 5, 7]);
+    #[rustfmt::skip]
+    let expect_normals = vec![
+        0.0, 0.0, 1.0, 0.0, 0.0, 1.0, 0.0, 0.0, 1.0, 0.0, 0.0, 1.0,
+        1.0, 0.0, 0.0, 1.0, 0.0, 0.0, 1.0, 0.0, 0.0, 1.0, 0.0, 0.0,
+    ];
+    assert_float_eq!(mesh.normals, expect_normals, abs_all <= TOL);
+
+    // Without smoothing groups the shared edge is smoothed, unless it's sharper
+    // than the crease angle.
+    let mut mesh = load(&tobj::LoadOptions::default());
+    mesh.smoothing_groups.clear();
+    mesh.generate_normals(smooth, false);
+    assert_eq!(mesh.normals.len(), 6 * 3);
+    assert_eq!(mesh.normal_indices, vec![0, 1, 2, 3, 1, 4, 5, 2]);
+    let diagonal = std::f64::consts::FRAC_1_SQRT_2 as tobj::Float;
+    assert_float_eq!(
+        mesh.normals[3..6],
+        [diagonal, 0.0, diagonal][..],
+        abs_all <= 1e-6
+    );
+
+    mesh.generate_normals(
+        tobj::NormalGeneration::Smooth {
+            weighting: tobj::NormalWeighting::Area,
+            crease_angle: Some(0.5),
+        },
+        false,
+    );
+    assert_eq!(mesh.normals.len(), 8 * 3);
+
+    let mesh = load(&tobj::LoadOptions {
+        generate_normals: tobj::NormalGeneration::Flat,
+        ..Default::default()
+    });
+    assert_eq!(mesh.normal_indices, vec![0, 1, 2, 3, 4, 5, 6, 7]);
+
+    // Vertices of single index meshes on a texture seam are smoothed across
+    // it, as they share their positions.
+    let obj = "
+v 0 0 0
+v 1 0 0
+v 1 1 0
+v 0 1 0
+v 1 0 -1
+v 1 1 -1
+vt 0 0
+vt 1 0
+vt 1 1
+vt 0 1
+s 1
+f 1/1 2/2 3/3 4/4
+f 2/1 5/2 6/3 3/4
+";
+    let (models, _, _) = tobj::load_obj_buf(
+        &mut Cursor::new(obj),
+        &tobj::LoadOptions {
+            single_index: true,
+            generate_normals: smooth,
+            ..Default::default()
+        },
+        |_| unreachable!(),
+    )
+    .expect("Failed to load OBJ buffer");
+    let mesh = &models[0].mesh;
+    assert_eq!(mesh.positions.len() / 3, 8);
+    assert_eq!(mesh.indices, vec![0, 1, 2, 3, 4, 5, 6, 7]);
+    for v in [1, 2, 4, 7] {
+        assert_float_eq!(
+            mesh.normals[v * 3..v * 3 + 3],
+            [diagonal, 0.0, diagonal][..],
+            abs_all <= 1e-6
+        );
+    }
+}
+
+#[test]
+#[cfg(feature = "tangents")]
+fn test_generate_tangents() {
+    // Two quads sharing an edge with mirrored texture coordinates.
+    let obj = "
+v 0 0 0
+v 1 0 0
+v 1 1 0
+v 0 1 0
+v 2 0 0
+v 2 1 0
+vt 0 0
+vt 1 0
+vt 1 1
+vt 0 1
+vn 0 0 1
+f 1/1/1 2/2/1 3/3/1 4/4/1
+f 2/2/1 5/1/1 6/4/1 3/3/1
+";
+    let load = |single_index| {
+        let (models, _, _) = tobj::load_obj_buf(
+            &mut Cursor::new(obj),
+            &tobj::LoadOptions {
+                single_index,
+                generate_tangents: true,
+                ..Default::default()
+            },
+            |_| unreachable!(),
+        )
+        .expect("Failed to load OBJ buffer");
+        models.into_iter().next().unwrap().mesh
+    };
+
+    // The vertices on the seam are split as their tangent spaces differ in
+    // handedness.
+    let mesh = load(true);
+    assert_eq!(mesh.positions.len() / 3, 8);
+    assert_eq!(mesh.tangents.len(), 8 * 4);
+    assert!(mesh.tangent_indices.is_empty());
+    for face in mesh.indices.chunks(4) {
+        let expect_tangent = if face == [0, 1, 2, 3] {
+            [1.0, 0.0, 0.0, 1.0]
+        } else {
+            [-1.0, 0.0, 0.0, -1.0]
+        };
+        for &v in face {
+            let v = v as usize * 4;
+            assert_float_eq!(mesh.tangents[v..v + 4], expect_tangent[..], abs_all <= TOL);
+        }
+    }
+
+    let mesh = load(false);
+    assert_eq!(mesh.positions.len() / 3, 6);
+    assert_eq!(mesh.tangent_indices, vec![0, 1, 2, 3, 4, 5, 6, 7]);
+    assert_float_eq!(
+        mesh.tangents[16..20],
+        [-1.0, 0.0, 0.0, -1.0][..],
+        abs_all <= TOL
+    );
+
+    // Identical tangents are merged like normals
+    #[cfg(feature = "merging")]
+    {
+        let (models, _, _) = tobj::load_obj_buf(
+            &mut Cursor::new(obj),
+            &tobj::LoadOptions {
+                generate_tangents: true,
+                merge_identical_points: true,
+                ..Default::default()
+            },
+            |_| unreachable!(),
+        )
+        .expect("Failed to load OBJ buffer");
+        let mesh = &models[0].mesh;
+        assert_eq!(mesh.tangents.len(), 2 * 4);
+        assert_eq!(mesh.tangent_indices, vec![0, 0, 0, 0, 1, 1, 1, 1]);
+    }
+}
+
+#[test]
+fn test_ear_clipping() {
+    // An L-shaped hexagon starting at a vertex which is not visible from its
+    // reflex corner, so a triangle fan would overlap.
+    let obj = "
+v 2 1 0
+v 1 1 0
+v 1 2 0
+v 0 2 0
+v 0 0 0
+v 2 0 0
+f 1 2 3 4 5 6
+";
+    for single_index in [true, false] {
+        let (models, _, _) = tobj::load_obj_buf(
+            &mut Cursor::new(obj),
+            &tobj::LoadOptions {
+                single_index,
+                triangulate: true,
+                triangulation: tobj::Triangulation::EarClipping,
+                ..Default::default()
+            },
+            |_| unreachable!(),
+        )
+        .expect("Failed to load OBJ buffer");
+        let mesh = &models[0].mesh;
+        assert_eq!(mesh.indices.len(), 4 * 3);
+
+        // All triangles wind counter-clockwise and cover the polygon exactly.
+        let mut area = 0.0;
+        for triangle in mesh.indices.chunks(3) {
+            let p = triangle
+                .iter()
+                .map(|&i| {
+                    [
+                        mesh.positions[i as usize * 3],
+                        mesh.positions[i as usize * 3 + 1],
+                    ]
+                })
+                .collect::<Vec<_>>();
+            let triangle_area = (p[1][0] - p[0][0]) * (p[2][1] - p[0][1])
+                - (p[1][1] - p[0][1]) * (p[2][0] - p[0][0]);
+            assert!(triangle_area > 0.0);
+            area += triangle_area / 2.0;
+        }
+        assert_float_eq!(area, 3.0, abs <= TOL);
+    }
+}
+
+#[test]
+fn test_write_obj() {
+    for single_index in [true, false] {
+        let load_options = tobj::LoadOptions {
+            single_index,
+            ..Default::default()
+        };
+        let (models, mats, _) = tobj::load_obj("obj/cornell_box.obj", &load_options).unwrap();
+        let mats = mats.unwrap();
+
+        let mut obj = Vec::new();
+        tobj::write_obj_buf(&mut obj, &models, &mats, Some("materials.mtl")).unwrap();
+
+        let (written_models, written_mats, _) =
+            tobj::load_obj_buf(&mut Cursor::new(obj), &load_options, |p| {
+                assert_eq!(p.to_str().unwrap(), "materials.mtl");
+                let mat_map = mats
+                    .iter()
+                    .enumerate()
+                    .map(|(i, mat)| (mat.name.clone(), i))
+                    .collect();
+                Ok((mats.clone(), mat_map, Vec::new()))
+            })
+            .unwrap();
+        let written_mats = written_mats.unwrap();
+
+        assert_eq!(models.len(), written_models.len());
+        for (model, written_model) in models.iter().zip(&written_models) {
+            let (mesh, written_mesh) = (&model.mesh, &written_model.mesh);
+            assert_eq!(model.name, written_model.name);
+            assert_eq!(
+                mesh.material_id.map(|id| &mats[id].name),
+                written_mesh.material_id.map(|id| &written_mats[id].name)
+            );
+            assert_eq!(mesh.positions, written_mesh.positions);
+            assert_eq!(mesh.indices, written_mesh.indices);
+            assert_eq!(mesh.face_arities, written_mesh.face_arities);
+        }
+    }
+
+    // Separate texture coordinate & normal indices and smoothing groups.
+    let load_options = tobj::LoadOptions::default();
+    for file in ["obj/quad.obj", "obj/smoothing_groups.obj"] {
+        let (models, _, _) = tobj::load_obj(file, &load_options).unwrap();
+        let mut obj = Vec::new();
+        tobj::write_obj_buf(&mut obj, &models, &[], None).unwrap();
+        let (written_models, _, _) =
+            tobj::load_obj_buf(&mut Cursor::new(obj), &load_options, |_| unreachable!()).unwrap();
+
+        for (model, written_model) in models.iter().zip(&written_models) {
+            let (mesh, written_mesh) = (&model.mesh, &written_model.mesh);
+            assert_eq!(mesh.positions, written_mesh.positions);
+            assert_eq!(mesh.texcoords, written_mesh.texcoords);
+            assert_eq!(mesh.normals, written_mesh.normals);
+            assert_eq!(mesh.indices, written_mesh.indices);
+            assert_eq!(mesh.texcoord_indices, written_mesh.texcoord_indices);
+            assert_eq!(mesh.normal_indices, written_mesh.normal_indices);
+            assert_eq!(mesh.smoothing_groups, written_mesh.smoothing_groups);
+        }
+    }
+
+    // The smoothing group and material carry over between models, so they are
+    // written when they change from one model to the next
+    let obj = "
+mtllib materials.mtl
+v 0 0 0
+v 1 0 0
+v 0 1 0
+o a
+usemtl red
+s 1
+f 1 2 3
+o b
+usemtl missing
+s off
+f 1 2 3
+o c
+f 1 2 3
+";
+    let load = |obj: &[u8]| {
+        let (models, _, _) =
+            tobj::load_obj_buf(&mut Cursor::new(obj), &tobj::LoadOptions::default(), |_| {
+                tobj::load_mtl_buf(&mut Cursor::new("newmtl red\nKd 1 0 0\n"))
+            })
+            .unwrap();
+        models
+    };
+    let mut models = load(obj.as_bytes());
+    models[1].mesh.smoothing_groups.clear();
+    let materials = tobj::load_mtl_buf(&mut Cursor::new("newmtl red\nKd 1 0 0\n"))
+        .unwrap()
+        .0;
+    let mut written = Vec::new();
+    tobj::write_obj_buf(&mut written, &models, &materials, Some("materials.mtl")).unwrap();
+    let written_models = load(&written);
+    let smoothing_groups = written_models
+        .iter()
+        .map(|model| model.mesh.smoothing_groups.clone())
+        .collect::<Vec<_>>();
+    assert_eq!(smoothing_groups, vec![vec![1], vec![0], vec![0]]);
+    let material_ids = written_models
+        .iter()
+        .map(|model| model.mesh.material_id)
+        .collect::<Vec<_>>();
+    assert_eq!(material_ids, vec![Some(0), None, None]);
+
+    // Materials that are not found are written with a generated name
+    models[0].mesh.material_id = Some(1);
+    let mut written = Vec::new();
+    tobj::write_obj_buf(&mut written, &models, &materials, None).unwrap();
+    let written = String::from_utf8(written).unwrap();
+    assert!(written.contains("usemtl material_1\n"));
+    assert!(tobj::write_obj_buf(&mut Vec::new(), &models, &[], None).is_ok());
+}
+
+#[test]
+fn test_write_mtl() {
+    for mtl in [CORNELL_BOX_MTL1, CORNELL_BOX_MTL2] {
+        let (mats, _, _) = tobj::load_mtl_buf(&mut Cursor::new(mtl)).unwrap();
+
+        let mut written = Vec::new();
+        tobj::write_mtl_buf(&mut written, &mats).unwrap();
+        let (written_mats, _, _) = tobj::load_mtl_buf(&mut Cursor::new(written)).unwrap();
+
+        assert_eq!(mats, written_mats);
+    }
+}
+
+#[test]
+fn test_texture_map_options() {
+    let mtl = "
+newmtl wood
+map_Kd -s 2 2 1 -clamp on wood with spaces.png
+map_Bump -bm 0.5 -imfchan l -o 0.1 -mm 0 1 -blendu off bump.png
+map_Ks -t 0.5 -texres 512 -type sphere -cc on -boost 2 -blendv on spec.png
+";
+    let (mats, _, _) = tobj::load_mtl_buf(&mut Cursor::new(mtl)).unwrap();
+    let mat = &mats[0];
+
+    let diffuse = mat.diffuse_texture.as_ref().unwrap();
+    assert_eq!(diffuse.path, "wood with spaces.png");
+    assert_eq!(diffuse.raw, "-s 2 2 1 -clamp on wood with spaces.png");
+    assert_eq!(diffuse.scale, Some([2.0, 2.0, 1.0]));
+    assert_eq!(diffuse.clamp, Some(true));
+    assert_eq!(diffuse.offset, None);
+
+    let normal = mat.normal_texture.as_ref().unwrap();
+    assert_eq!(normal.path, "bump.png");
+    assert_eq!(normal.bump_multiplier, Some(0.5));
+    assert_eq!(normal.imfchan, Some('l'));
+    assert_eq!(normal.offset, Some([0.1, 0.0, 0.0]));
+    assert_eq!(normal.mm, Some([0.0, 1.0]));
+    assert_eq!(normal.blend_u, Some(false));
+
+    let specular = mat.specular_texture.as_ref().unwrap();
+    assert_eq!(specular.path, "spec.png");
+    assert_eq!(specular.turbulence, Some([0.5, 0.0, 0.0]));
+    assert_eq!(specular.texture_resolution, Some(512));
+    assert_eq!(specular.texture_type.as_deref(), Some("sphere"));
+    assert_eq!(specular.color_correction, Some(true));
+    assert_eq!(specular.boost, Some(2.0));
+    assert_eq!(specular.blend_v, Some(true));
+
+    // Options are written back out with the texture, apart from the raw
+    // statement
+    let without_raw = |mats: &[tobj::Material]| {
+        let mut mats = mats.to_vec();
+        for mat in &mut mats {
+            let textures = [
+                &mut mat.diffuse_texture,
+                &mut mat.normal_texture,
+                &mut mat.specular_texture,
+            ];
+            for texture in textures.into_iter().flatten() {
+                texture.raw.clear();
+            }
+        }
+        mats
+    };
+    let mut written = Vec::new();
+    tobj::write_mtl_buf(&mut written, &mats).unwrap();
+    let (written_mats, _, _) = tobj::load_mtl_buf(&mut Cursor::new(written)).unwrap();
+    assert_eq!(without_raw(&mats), without_raw(&written_mats));
+
+    // Changes to the options and path of loaded texture maps are written
+    let mut mats = mats;
+    let diffuse = mats[0].diffuse_texture.as_mut().unwrap();
+    diffuse.path = "oak.png".to_owned();
+    diffuse.clamp = Some(false);
+    let mut written = Vec::new();
+    tobj::write_mtl_buf(&mut written, &mats).unwrap();
+    let (written_mats, _, _) = tobj::load_mtl_buf(&mut Cursor::new(written)).unwrap();
+    let written_diffuse = written_mats[0].diffuse_texture.as_ref().unwrap();
+    assert_eq!(written_diffuse.path, "oak.png");
+    assert_eq!(written_diffuse.scale, Some([2.0, 2.0, 1.0]));
+    assert_eq!(written_diffuse.clamp, Some(false));
+
+    // The file name is kept if an option is missing its value
+    for (mtl, path) in [
+        ("newmtl a\nmap_Kd -bm tex.png", "tex.png"),
+        ("newmtl a\nmap_Kd -clamp on -type tex.png", "tex.png"),
+        ("newmtl a\nmap_Kd -clamp on", "on"),
+    ] {
+        let (mats, _, warnings) = tobj::load_mtl_buf(&mut Cursor::new(mtl)).unwrap();
+        let diffuse = mats[0].diffuse_texture.as_ref().unwrap();
+        assert_eq!(diffuse.path, path);
+        assert_eq!(diffuse.bump_multiplier, None);
+        assert_eq!(diffuse.texture_type, None);
+        assert_eq!(warnings.len(), 1);
+    }
+
+    // Options with invalid values are skipped when loading, with a warning
+    let mtl = "newmtl a\nmap_Kd -bm x -clamp on wood.png\n";
+    let (mats, _, warnings) = tobj::load_mtl_buf(&mut Cursor::new(mtl)).unwrap();
+    let diffuse = mats[0].diffuse_texture.as_ref().unwrap();
+    assert_eq!(diffuse.path, "wood.png");
+    assert_eq!(diffuse.raw, "-bm x -clamp on wood.png");
+    assert_eq!(diffuse.bump_multiplier, None);
+    assert_eq!(diffuse.clamp, Some(true));
+    assert!(diffuse.raw.parse::<tobj::TextureMap>().is_err());
+    let invalid_options = vec![tobj::LoadWarning::InvalidTextureOptions {
+        material: "a".to_owned(),
+        keyword: "map_Kd".to_owned(),
+    }];
+    assert_eq!(warnings, invalid_options);
+    // They are not written, so the written texture map is valid
+    let mut written = Vec::new();
+    tobj::write_mtl_buf(&mut written, &mats).unwrap();
+    let (written_mats, _, warnings) = tobj::load_mtl_buf(&mut Cursor::new(written)).unwrap();
+    assert_eq!(without_raw(&mats), without_raw(&written_mats));
+    assert!(warnings.is_empty());
+    let (_, _, warnings) = tobj::load_obj_buf(
+        &mut Cursor::new("mtllib a.mtl\n"),
+        &tobj::LoadOptions::default(),
+        |_| tobj::load_mtl_buf(&mut Cursor::new(mtl)),
+    )
+    .unwrap();
+    assert_eq!(warnings, invalid_options);
+
+    // Texture maps created in code have no raw statement, which is fine
+    let mat = tobj::Material {
+        name: "a".to_owned(),
+        diffuse_texture: Some(tobj::TextureMap {
+            path: "wood.png".to_owned(),
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+    let (_, _, warnings) = tobj::load_obj_buf(
+        &mut Cursor::new("mtllib a.mtl\n"),
+        &tobj::LoadOptions::default(),
+        |_| Ok((vec![mat.clone()], Default::default(), Vec::new())),
+    )
+    .unwrap();
+    assert!(warnings.is_empty());
+}
+
+#[test]
+fn test_pbr_material() {
+    let (mats, _, _) = tobj::load_mtl("obj/pbr.mtl").unwrap();
+    let mat = &mats[0];
+    assert_eq!(mat.roughness, Some(0.25));
+    assert_eq!(mat.metallic, Some(1.0));
+    assert_eq!(mat.sheen, Some(0.1));
+    assert_eq!(mat.clearcoat_thickness, Some(0.5));
+    assert_eq!(mat.clearcoat_roughness, Some(0.03));
+    assert_eq!(mat.anisotropy, Some(0.2));
+    assert_eq!(mat.anisotropy_rotation, Some(0.75));
+
+    let path = |texture: &Option<tobj::TextureMap>| texture.as_ref().map(|t| t.path.clone());
+    assert_eq!(
+        path(&mat.roughness_texture).as_deref(),
+        Some("roughness.png")
+    );
+    assert_eq!(path(&mat.metallic_texture).as_deref(), Some("metallic.png"));
+    assert_eq!(path(&mat.sheen_texture).as_deref(), Some("sheen.png"));
+    assert_eq!(path(&mat.emissive_texture).as_deref(), Some("emissive.png"));
+    assert_eq!(path(&mat.normal_map_texture).as_deref(), Some("normal.png"));
+    assert_eq!(path(&mat.normal_texture).as_deref(), Some("bump.png"));
+    assert_eq!(
+        mat.normal_map_texture.as_ref().unwrap().bump_multiplier,
+        Some(2.0)
+    );
+    assert!(mat.unknown_param.is_empty());
+
+    let mut written = Vec::new();
+    tobj::write_mtl_buf(&mut written, &mats).unwrap();
+    let (written_mats, _, _) = tobj::load_mtl_buf(&mut Cursor::new(written)).unwrap();
+    assert_eq!(written_mats[0].roughness, mat.roughness);
+    assert_eq!(written_mats[0].anisotropy_rotation, mat.anisotropy_rotation);
+    assert_eq!(
+        path(&written_mats[0].normal_map_texture),
+        path(&mat.normal_map_texture)
+    );
+}
+
+#[test]
+fn test_per_face_materials() {
+    let obj = "
+mtllib materials.mtl
+v 0 0 0
+v 1 0 0
+v 1 1 0
+v 0 1 0
+o alternating
+usemtl red
+f 1 2 3
+usemtl blue
+f 1 3 4
+usemtl red
+f 2 3 4
+o plain
+usemtl blue
+f 1 2 3 4
+";
+    let mtl = "
+newmtl red
+Kd 1 0 0
+newmtl blue
+Kd 0 0 1
+";
+    let load = |per_face_materials| {
+        tobj::load_obj_buf(
+            &mut Cursor::new(obj),
+            &tobj::LoadOptions {
+                triangulate: true,
+                per_face_materials,
+                ..Default::default()
+            },
+            |_| tobj::load_mtl_buf(&mut Cursor::new(mtl)),
+        )
+        .unwrap()
+        .0
+    };
+
+    // By default every material change starts a new model
+    let models = load(false);
+    assert_eq!(models.len(), 4);
+    assert!(models[..3].iter().all(|m| m.name == "alternating"));
+    assert!(models.iter().all(|m| m.mesh.face_material_ids.is_empty()));
+
+    let models = load(true);
+    assert_eq!(models.len(), 2);
+    let mesh = &models[0].mesh;
+    assert_eq!(models[0].name, "alternating");
+    assert_eq!(mesh.material_id, None);
+    assert_eq!(mesh.face_material_ids, vec![Some(0), Some(1), Some(0)]);
+    // The vertices are shared between the faces
+    assert_eq!(mesh.positions.len(), 4 * 3);
+    let mesh = &models[1].mesh;
+    assert_eq!(mesh.material_id, Some(1));
+    assert_eq!(mesh.face_material_ids, vec![Some(1), Some(1)]);
+
+    // The writer switches materials between faces, resetting the material for
+    // faces without one
+    let mut written = Vec::new();
+    tobj::write_obj_buf(&mut written, &models[..1], &[], None).unwrap();
+    let written = String::from_utf8(written).unwrap();
+    assert_eq!(written.matches("usemtl material_0").count(), 2);
+    assert_eq!(written.matches("usemtl material_1").count(), 1);
+    let materials = tobj::load_mtl_buf(&mut Cursor::new(mtl)).unwrap().0;
+    let mut written = Vec::new();
+    tobj::write_obj_buf(&mut written, &models[..1], &materials, None).unwrap();
+    let written = String::from_utf8(written).unwrap();
+    assert_eq!(written.matches("usemtl").count(), 3);
+    let mut models = models;
+    models[0].mesh.face_material_ids[2] = None;
+    let mut written = Vec::new();
+    tobj::write_obj_buf(&mut written, &models, &materials, Some("materials.mtl")).unwrap();
+    let (written_models, _, warnings) = tobj::load_obj_buf(
+        &mut Cursor::new(written),
+        &tobj::LoadOptions {
+            per_face_materials: true,
+            ..Default::default()
+        },
+        |_| tobj::load_mtl_buf(&mut Cursor::new(mtl)),
+    )
+    .unwrap();
+    assert_eq!(
+        written_models[0].mesh.face_material_ids,
+        vec![Some(0), Some(1), None]
+    );
+    // Resetting the material isn't an unresolved material
+    assert!(warnings.is_empty());
+    assert_eq!(written_models[1].mesh.material_id, Some(1));
+}
+
+#[test]
+fn test_load_error_context() {
+    let load = |obj: &str| {
+        tobj::load_obj_buf(
+            &mut Cursor::new(obj),
+            &tobj::LoadOptions::default(),
+            |_| unreachable!(),
+        )
+        .unwrap_err()
+    };
+
+    let err = load("v 0 0 0\nv 1 0 0\nv 0 1 0\n\nf 1 2 x\n");
+    assert_eq!(err.kind(), tobj::LoadErrorKind::FaceParseError);
+    assert_eq!(err.line(), Some(5));
+    assert_eq!(err.token(), Some("x"));
+    assert_eq!(err.path(), None);
+    assert_eq!(err.to_string(), "face parse error at line 5 near `x`");
+
+    let err = load("v 0 0\n");
+    assert_eq!(err, tobj::LoadErrorKind::PositionParseError);
+    assert_eq!(err.token(), Some("v"));
+
+    // Out of bounds indices are reported at the face, not where the model ends
+    let err = load("v 0 0 0\nf 1 2 3\no next\n");
+    assert_eq!(err, tobj::LoadErrorKind::FaceVertexOutOfBounds);
+    assert_eq!(err.line(), Some(2));
+
+    let err = tobj::load_obj("obj/invalid_index.obj", &tobj::LoadOptions::default()).unwrap_err();
+    assert_eq!(
+        err.path(),
+        Some(std::path::Path::new("obj/invalid_index.obj"))
+    );
+
+    let err = tobj::load_mtl("obj/missing.mtl").unwrap_err();
+    assert_eq!(err, tobj::LoadErrorKind::OpenFileFailed);
+    assert_eq!(err.path(), Some(std::path::Path::new("obj/missing.mtl")));
+    assert_eq!(
+        err.io_error().map(std::io::Error::kind),
+        Some(std::io::ErrorKind::NotFound)
+    );
+    assert!(std::error::Error::source(&err).is_some());
+
+    let err = tobj::load_mtl_buf(&mut Cursor::new("newmtl a\nKd 1 0\n")).unwrap_err();
+    assert_eq!(err, tobj::LoadErrorKind::MaterialParseError);
+    assert_eq!(err.line(), Some(2));
+    assert_eq!(err.token(), Some("Kd"));
+}
+
+#[test]
+fn test_skip_invalid_lines() {
+    let obj = "
+v 0 0 0
+v 1 0 0
+v 1 1 x
+v 1 1 0
+v 0 1 0
+f 1 2 3
+f 1 2 q
+usemtl
+f 1 3 4
+f 1 4 9
+";
+    let load_options = tobj::LoadOptions {
+        skip_invalid_lines: true,
+        ..Default::default()
+    };
+    let (models, _, warnings) =
+        tobj::load_obj_buf(&mut Cursor::new(obj), &load_options, |_| unreachable!()).unwrap();
+
+    // The invalid position is replaced by zeros, so it doesn't shift the
+    // following ones
+    let mesh = &models[0].mesh;
+    assert_eq!(mesh.positions.len(), 4 * 3);
+    assert_eq!(mesh.indices, vec![0, 1, 2, 0, 2, 3]);
+    assert_eq!(mesh.positions[6..9], [0.0; 3]);
+    assert_eq!(mesh.positions[9..12], [1.0, 1.0, 0.0]);
+
+    let skipped = warnings
+        .iter()
+        .filter_map(|warning| match warning {
+            tobj::LoadWarning::SkippedLine(e) => Some((e.kind(), e.line().unwrap())),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        skipped,
+        vec![
+            (tobj::LoadErrorKind::PositionParseError, 4),
+            (tobj::LoadErrorKind::FaceParseError, 8),
+            (tobj::LoadErrorKind::MaterialParseError, 9),
+            (tobj::LoadErrorKind::FaceVertexOutOfBounds, 11),
+        ]
+    );
+
+    // Without the option the first invalid line fails the load
+    let err = tobj::load_obj_buf(
+        &mut Cursor::new(obj),
+        &tobj::LoadOptions::default(),
+        |_| unreachable!(),
+    )
+    .unwrap_err();
+    assert_eq!(err.line(), Some(4));
+}
+
+#[test]
+fn test_load_warnings() {
+    let obj = "
+mtllib materials.mtl
+v 0 0 0
+v 1 0 0
+v 2 0 0
+v 0 1 0
+#comment
+bevel on
+usemtl missing
+f 1 2 4
+f 1 2 3
+f 1
+l 1 2
+";
+    let mtl = "
+newmtl mat
+Kd 1 1 1
+Pz 4
+";
+    let (_, _, warnings) = tobj::load_obj_buf(
+        &mut Cursor::new(obj),
+        &tobj::LoadOptions {
+            ignore_points: true,
+            ignore_lines: true,
+            ..Default::default()
+        },
+        |_| tobj::load_mtl_buf(&mut Cursor::new(mtl)),
+    )
+    .unwrap();
+    assert_eq!(
+        warnings,
+        vec![
+            tobj::LoadWarning::IgnoredDirective {
+                line: 8,
+                directive: "bevel".to_owned(),
+            },
+            tobj::LoadWarning::UnresolvedMaterial {
+                line: 9,
+                object: "unnamed_object".to_owned(),
+                material: "missing".to_owned(),
+            },
+            tobj::LoadWarning::DegenerateFace { line: 11 },
+            tobj::LoadWarning::DiscardedPoint { line: 12 },
+            tobj::LoadWarning::DiscardedLine { line: 13 },
+            tobj::LoadWarning::UnknownMaterialParameter {
+                material: "mat".to_owned(),
+                key: "Pz".to_owned(),
+            },
+        ]
+    );
+    assert_eq!(warnings[3].line(), Some(12));
+    assert_eq!(warnings[5].line(), None);
+
+    // Files without problems load without warnings
+    let (_, _, warnings) = tobj::load_obj("obj/quad.obj", &tobj::LoadOptions::default()).unwrap();
+    assert!(warnings.is_empty());
+}
+
+#[test]
+fn test_objects_and_groups() {
+    let obj = "
+v 0 0 0
+v 1 0 0
+v 0 1 0
+o Character Body
+g torso upper
+f 1 2 3
+g left_arm
+f 1 2 3
+o Head
+f 1 2 3
+g jaw
+f 1 2 3
+o Head
+f 1 2 3
+";
+    let load = |obj: &[u8]| {
+        tobj::load_obj_buf(
+            &mut Cursor::new(obj),
+            &tobj::LoadOptions::default(),
+            |_| unreachable!(),
+        )
+        .unwrap()
+        .0
+    };
+    let hierarchy = |models: &[tobj::Model]| {
+        models
+            .iter()
+            .map(|m| (m.name.clone(), m.object.clone(), m.groups.clone()))
+            .collect::<Vec<_>>()
+    };
+    let object = |name: &str| Some(name.to_owned());
+    let groups = |names: &[&str]| names.iter().map(|&n| n.to_owned()).collect::<Vec<_>>();
+    let expected = vec![
+        (
+            "torso upper".to_owned(),
+            object("Character Body"),
+            groups(&["torso", "upper"]),
+        ),
+        (
+            "left_arm".to_owned(),
+            object("Character Body"),
+            groups(&["left_arm"]),
+        ),
+        ("Head".to_owned(), object("Head"), groups(&[])),
+        ("jaw".to_owned(), object("Head"), groups(&["jaw"])),
+        // Back in no group of the object
+        ("Head".to_owned(), object("Head"), groups(&[])),
+    ];
+
+    let models = load(obj.as_bytes());
+    assert_eq!(hierarchy(&models), expected);
+
+    // The hierarchy survives writing the models
+    let mut written = Vec::new();
+    tobj::write_obj_buf(&mut written, &models, &[], None).unwrap();
+    assert_eq!(hierarchy(&load(&written)), expected);
+}
+
+#[test]
+fn test_scene() {
+    let obj = "
+mtllib materials.mtl
+v 0 0 0
+v 1 0 0
+v 0 1 0
+f 1 2 3
+o Body
+g torso
+usemtl red
+f 1 2 3
+usemtl blue
+f 1 2 3
+g arm
+f 1 2 3
+o Head
+usemtl red
+f 1 2 3
+o Body
+g torso
+f 1 2 3
+";
+    let mtl = "
+newmtl red
+Kd 1 0 0
+newmtl blue
+Kd 0 0 1
+";
+    let (models, _, _) =
+        tobj::load_obj_buf(&mut Cursor::new(obj), &tobj::LoadOptions::default(), |_| {
+            tobj::load_mtl_buf(&mut Cursor::new(mtl))
+        })
+        .unwrap();
+    assert_eq!(models.len(), 6);
+
+    let scene = tobj::Scene::from_models(models);
+    let objects = &scene.objects;
+    assert_eq!(objects.len(), 3);
+    assert_eq!(objects[0].name, None);
+    assert_eq!(objects[1].name.as_deref(), Some("Body"));
+    assert_eq!(objects[2].name.as_deref(), Some("Head"));
+
+    let groups = &objects[1].groups;
+    assert_eq!(groups.len(), 2);
+    assert_eq!(groups[0].names, vec!["torso"]);
+    assert_eq!(groups[1].names, vec!["arm"]);
+    assert_eq!(groups.iter().map(|g| g.id).collect::<Vec<_>>(), vec![1, 2]);
+
+    let submeshes = &groups[0].submeshes;
+    assert_eq!(submeshes.len(), 3);
+    assert_eq!(submeshes[0].id, 1);
+    assert_eq!(submeshes[0].mesh.material_id, Some(0));
+    assert_eq!(submeshes[1].id, 2);
+    assert_eq!(submeshes[1].mesh.material_id, Some(1));
+    // The material carries over to the next group
+    assert_eq!(groups[1].submeshes[0].mesh.material_id, Some(1));
+    assert_eq!(objects[2].groups[0].submeshes[0].id, 4);
+    // Returning to an object and group continues them
+    assert_eq!(submeshes[2].id, 5);
+    assert_eq!(submeshes[2].mesh.material_id, Some(0));
+}
+
+#[test]
+fn test_load_obj_raw() {
+    let obj = "
+v 0 0 0
+v 1 0 0
+v 1 1 0
+v 0 1 0
+vt 0 0
+vt 1 1
+o first
+f 1/1 2/2 3/1 4/2
+o second
+f -4 -2 -1
+";
+    let (raw, _, _) = tobj::load_obj_buf_raw(
+        &mut Cursor::new(obj),
+        &tobj::LoadOptions {
+            triangulate: true,
+            ..Default::default()
+        },
+        |_| unreachable!(),
+    )
+    .unwrap();
+
+    // The vertex data is shared instead of exported per shape
+    assert_eq!(raw.positions.len(), 4 * 3);
+    assert_eq!(raw.texcoords.len(), 2 * 2);
+    assert!(raw.normals.is_empty());
+
+    let shapes = &raw.shapes;
+    assert_eq!(shapes.len(), 2);
+    assert_eq!(shapes[0].name, "first");
+    // Faces are not triangulated
+    assert_eq!(shapes[0].face_arities, vec![4]);
+    let vertex = |v, vt| tobj::VertexIndices {
+        v,
+        vt,
+        vn: tobj::MISSING_INDEX,
+    };
+    assert_eq!(
+        shapes[0].indices,
+        vec![vertex(0, 0), vertex(1, 1), vertex(2, 0), vertex(3, 1)]
+    );
+    assert_eq!(shapes[1].name, "second");
+    assert_eq!(shapes[1].face_arities, vec![3]);
+    let missing = tobj::MISSING_INDEX;
+    assert_eq!(
+        shapes[1].indices,
+        vec![vertex(0, missing), vertex(2, missing), vertex(3, missing)]
+    );
+
+    let (raw, _, _) = tobj::load_obj_raw("obj/quad.obj", &tobj::LoadOptions::default()).unwrap();
+    let (models, _, _) = tobj::load_obj("obj/quad.obj", &tobj::LoadOptions::default()).unwrap();
+    assert_eq!(raw.shapes.len(), models.len());
+}
+
+#[test]
+fn test_source_vertices() {
+    let obj = "
+v 0 0 0
+v 1 0 0
+v 1 1 0
+v 0 1 0
+vt 0 0
+vt 1 0
+vt 1 1
+vn 0 0 1
+f 1/1/1 2/2/1 3/3/1
+f 1/1/1 3/3/1 4/2/1
+";
+    let load = |single_index| {
+        let load_options = tobj::LoadOptions {
+            single_index,
+            source_vertices: true,
+            ..Default::default()
+        };
+        tobj::load_obj_buf(&mut Cursor::new(obj), &load_options, |_| unreachable!())
+            .unwrap()
+            .0
+            .remove(0)
+            .mesh
+    };
+    let vertex = |v, vt| tobj::VertexIndices { v, vt, vn: 0 };
+
+    // One entry per vertex
+    let mesh = load(true);
+    assert_eq!(mesh.positions.len(), 4 * 3);
+    assert_eq!(
+        mesh.source_vertices,
+        vec![vertex(0, 0), vertex(1, 1), vertex(2, 2), vertex(3, 1)]
+    );
+
+    // One entry per index
+    let mesh = load(false);
+    assert_eq!(mesh.indices, vec![0, 1, 2, 0, 2, 3]);
+    assert_eq!(
+        mesh.source_vertices,
+        vec![
+            vertex(0, 0),
+            vertex(1, 1),
+            vertex(2, 2),
+            vertex(0, 0),
+            vertex(2, 2),
+            vertex(3, 1)
+        ]
+    );
+
+    // Merging and reordering renumber the elements of the mesh, the sources
+    // still refer to the data each corner has
+    #[cfg(any(feature = "merging", feature = "reordering"))]
+    {
+        // The first and last positions are merged, reordering needs normals
+        let obj = "
+v 0 0 0
+v 1 0 0
+v 1 1 0
+v 0 1 0
+v 0 0 0
+vt 0 0
+vt 1 0
+vt 2 0
+vt 3 0
+vt 4 0
+vt 5 0
+vt 6 0
+vt 7 0
+vt 8 0
+vn 0 0 0
+vn 1 0 0
+vn 2 0 0
+vn 3 0 0
+vn 4 0 0
+vn 5 0 0
+vn 6 0 0
+vn 7 0 0
+vn 8 0 0
+f 1/1/1 2/2/2 3/3/3
+f 1/4/4 3/5/5 4/6/6
+f 5/7/7 3/8/8 4/9/9
+";
+        let positions = [
+            [0.0, 0.0, 0.0],
+            [1.0, 0.0, 0.0],
+            [1.0, 1.0, 0.0],
+            [0.0, 1.0, 0.0],
+            [0.0, 0.0, 0.0],
+        ];
+        let check = |load_options: tobj::LoadOptions| {
+            let load_options = tobj::LoadOptions {
+                source_vertices: true,
+                ..load_options
+            };
+            let (models, _, _) =
+                tobj::load_obj_buf(&mut Cursor::new(obj), &load_options, |_| unreachable!())
+                    .unwrap();
+            let mesh = &models[0].mesh;
+            assert_eq!(mesh.source_vertices.len(), mesh.indices.len());
+            for (corner, source) in mesh.source_vertices.iter().enumerate() {
+                let v = mesh.indices[corner] as usize;
+                assert_eq!(mesh.positions[v * 3..v * 3 + 3], positions[source.v]);
+                let vt = if !mesh.texcoord_indices.is_empty() {
+                    mesh.texcoord_indices[corner] as usize
+                } else if mesh.texcoords.len() / 2 == mesh.positions.len() / 3 {
+                    v
+                } else {
+                    corner
+                };
+                assert_eq!(
+                    mesh.texcoords[vt * 2..vt * 2 + 2],
+                    [source.vt as Float, 0.0]
+                );
+                let vn = if !mesh.normal_indices.is_empty() {
+                    mesh.normal_indices[corner] as usize
+                } else if mesh.normals.len() == mesh.positions.len() {
+                    v
+                } else {
+                    corner
+                };
+                assert_eq!(
+                    mesh.normals[vn * 3..vn * 3 + 3],
+                    [source.vn as Float, 0.0, 0.0]
+                );
+            }
+            mesh.source_vertices.clone()
+        };
+        let vertex = |v, vt| tobj::VertexIndices { v, vt, vn: vt };
+        let expected = vec![
+            vertex(0, 0),
+            vertex(1, 1),
+            vertex(2, 2),
+            vertex(0, 3),
+            vertex(2, 4),
+            vertex(3, 5),
+            vertex(4, 6),
+            vertex(2, 7),
+            vertex(3, 8),
+        ];
+        #[cfg(feature = "merging")]
+        assert_eq!(
+            check(tobj::LoadOptions {
+                merge_identical_points: true,
+                ..Default::default()
+            }),
+            expected
+        );
+        #[cfg(feature = "reordering")]
+        assert_eq!(
+            check(tobj::LoadOptions {
+                reorder_data: true,
+                ..Default::default()
+            }),
+            expected
+        );
+    }
+
+    let (models, _, _) = tobj::load_obj("obj/quad.obj", &tobj::LoadOptions::default()).unwrap();
+    assert!(models[0].mesh.source_vertices.is_empty());
+}
+
+#[test]
+fn test_source_faces() {
+    let obj = "
+v 0 0 0
+v 1 0 0
+v 1 1 0
+v 0 1 0
+v 0.5 2 0
+f 1 2 3
+f 1
+f 1 2 3 5 4
+f 1 3 4
+";
+    let load = |triangulate| {
+        let load_options = tobj::LoadOptions {
+            triangulate,
+            ignore_points: true,
+            source_faces: true,
+            ..Default::default()
+        };
+        tobj::load_obj_buf(&mut Cursor::new(obj), &load_options, |_| unreachable!())
+            .unwrap()
+            .0
+            .remove(0)
+            .mesh
+    };
+
+    // The point is not a face of its own
+    let mesh = load(true);
+    assert_eq!(mesh.indices.len() / 3, 5);
+    assert_eq!(mesh.source_faces, vec![0, 1, 1, 1, 2]);
+
+    let mesh = load(false);
+    assert_eq!(mesh.face_arities, vec![3, 5, 3]);
+    assert_eq!(mesh.source_faces, vec![0, 1, 2]);
+}
+
+#[test]
+fn test_free_form() {
+    let obj = "
+v 0 0 0
+v 1 0 0
+v 0 1 0
+v 1 1 0 2
+vt 0 0
+vp 0 0
+vp 1 0
+vp 1 1
+vp 0 1
+vp 0.5
+o curves
+cstype bspline
+deg 1
+curv2 1 2 3 4 1
+parm u 0 0 1 2 3 4 4
+end
+curv2 -5 -3
+parm u 0 0 1 1
+end
+curv 0 1 1 4
+parm u 0 0 1 1
+sp 5
+end
+o patch
+cstype rat bspline
+deg 1 1
+surf 0 1 0 1 1/1 2/1 3/1 4/1
+parm u 0 0 1 1
+parm v 0 0 1 1
+trim 0 1 1 0 1 2
+hole 0 1 -1
+end
+o mesh
+f 1 2 3
+";
+    let (models, _, _) = tobj::load_obj_buf(
+        &mut Cursor::new(obj),
+        &tobj::LoadOptions::default(),
+        |_| unreachable!(),
+    )
+    .unwrap();
+    assert_eq!(models.len(), 3);
+    assert!(models[1].mesh.indices.is_empty());
+    assert!(models[2].free_form.is_empty());
+
+    let free_form = &models[0].free_form;
+    assert_eq!(free_form.curves_2d.len(), 2);
+    assert_eq!(free_form.curves.len(), 1);
+    let curve = &free_form.curves_2d[0];
+    assert!(!curve.basis.rational);
+    assert_eq!(curve.basis.degree, [1, 0]);
+    assert_eq!(curve.control_points[2], [1.0, 1.0, 1.0]);
+    assert_eq!(curve.knots.len(), 7);
+    assert_eq!(free_form.curves_2d[1].control_points[0], [0.0, 0.0, 1.0]);
+    // The w of positions is the weight of control points
+    assert_eq!(free_form.curves[0].control_points[1], [1.0, 1.0, 0.0, 2.0]);
+    assert_eq!(free_form.curves[0].special_points, vec![[0.5, 0.0, 1.0]]);
+
+    let surface = &models[1].free_form.surfaces[0];
+    assert_eq!(surface.basis.basis_type, tobj::BasisType::BSpline);
+    assert!(surface.basis.rational);
+    assert_eq!(surface.basis.degree, [1, 1]);
+    assert_eq!(surface.parameter_range, [[0.0, 1.0], [0.0, 1.0]]);
+    assert_eq!(surface.control_points[3], [1.0, 1.0, 0.0, 2.0]);
+    assert_eq!(surface.control_points[2], [0.0, 1.0, 0.0, 1.0]);
+    assert_eq!(surface.texcoords.len(), 4);
+    assert!(surface.normals.is_empty());
+    assert_eq!(surface.knots[1], vec![0.0, 0.0, 1.0, 1.0]);
+    // Trimming loops may refer to the curves of other objects
+    assert_eq!(surface.trims.len(), 1);
+    assert_eq!(surface.trims[0].len(), 2);
+    assert_eq!(surface.holes[0][0].curve, free_form.curves_2d[1]);
+
+    // Body statements are only valid within a body
+    let err = tobj::load_obj_buf(
+        &mut Cursor::new("vp 0\nparm u 0 1\n"),
+        &tobj::LoadOptions::default(),
+        |_| unreachable!(),
+    )
+    .unwrap_err();
+    assert_eq!(err, tobj::LoadErrorKind::FreeFormParseError);
+    assert_eq!(err.line(), Some(2));
+
+    // Knots have to be finite and non-decreasing
+    for parm in ["parm u 0 1 0.5", "parm u 0 NaN 1", "parm u 0 inf"] {
+        let obj = format!("v 0 0 0\nv 1 0 0\ncstype bezier\ndeg 1\ncurv 0 1 1 2\n{parm}\n");
+        let err = tobj::load_obj_buf(
+            &mut Cursor::new(obj),
+            &tobj::LoadOptions::default(),
+            |_| unreachable!(),
+        )
+        .unwrap_err();
+        assert_eq!(err, tobj::LoadErrorKind::FreeFormParseError);
+        assert_eq!(err.line(), Some(6));
+    }
+}
+
+#[test]
+fn test_tessellate_surfaces() {
+    let obj = "
+v 0 0 0
+v 1 0 1
+v 2 0 0
+v 0 1 0
+v 1 1 1
+v 2 1 0
+vp 0.4 0.4
+vp 0.6 0.4
+vp 0.6 0.6
+vp 0.4 0.6
+cstype bspline
+deg 1
+curv2 1 2 3 4 1
+parm u 0 0 1 2 3 4 4
+end
+deg 2 1
+surf 0 1 0 1 1 2 3 4 5 6
+parm u 0 0 0 1 1 1
+parm v 0 0 1 1
+end
+surf 0 1 0 1 1 2 3 4 5 6
+parm u 0 0 0 1 1 1
+parm v 0 0 1 1
+hole 0 4 1
+end
+surf 0 1 0 1 1 2 3
+end
+";
+    let load = |resolution| {
+        tobj::load_obj_buf(
+            &mut Cursor::new(obj),
+            &tobj::LoadOptions {
+                single_index: true,
+                tessellate_surfaces: resolution,
+                ..Default::default()
+            },
+            |_| unreachable!(),
+        )
+        .unwrap()
+    };
+
+    let (models, _, warnings) = load(None);
+    assert!(models[0].mesh.indices.is_empty());
+    assert!(warnings.is_empty());
+
+    let (models, _, warnings) = load(Some(10));
+    let surfaces = &models[0].free_form.surfaces;
+    assert_eq!(
+        warnings,
+        vec![tobj::LoadWarning::UntessellatedSurface { line: 27 }]
+    );
+    assert!(surfaces[2].tessellate(10).is_none());
+    // Surfaces with invalid knots can't be tessellated either
+    let mut surface = surfaces[0].clone();
+    surface.knots[0] = vec![0.0, 0.0, 0.0, 1.0, 0.5, 1.0];
+    assert!(surface.tessellate(10).is_none());
+    surface.knots[0][4] = tobj::Float::NAN;
+    assert!(surface.tessellate(10).is_none());
+
+    let mesh = surfaces[0].tessellate(2).unwrap();
+    assert_eq!(mesh.positions.len(), 3 * 9);
+    assert_eq!(mesh.indices.len(), 3 * 8);
+    assert!(mesh.texcoords.is_empty());
+    // The middle of the surface, the quadratic basis halves the height of the
+    // middle control points
+    assert_eq!(&mesh.positions[12..15], &[1.0, 0.5, 0.5]);
+    assert_eq!(&mesh.normals[12..15], &[0.0, 0.0, 1.0]);
+
+    // The hole cuts out the triangles around the middle of the surface
+    let full = surfaces[0].tessellate(10).unwrap();
+    let trimmed = surfaces[1].tessellate(10).unwrap();
+    assert_eq!(full.indices.len(), 3 * 200);
+    assert_eq!(trimmed.indices.len(), 3 * (200 - 8));
+    assert!(trimmed.positions.len() < full.positions.len());
+
+    // Loading adds the triangles of both surfaces to the mesh
+    let mesh = &models[0].mesh;
+    assert_eq!(
+        mesh.indices.len(),
+        full.indices.len() + trimmed.indices.len()
+    );
+    assert_eq!(mesh.normals.len(), mesh.positions.len());
+}
+
+#[test]
+fn test_polylines() {
+    let obj = "
+v 0 0 0
+v 1 0 0
+v 1 1 0
+v 0 1 0
+vt 0 0
+vt 1 0
+vt 0.5 1
+f 1/1 2/2 3/3
+l 1/2 2/1 3/3 4/1 1/2
+f 3 4
+";
+    let load = |load_options| {
+        tobj::load_obj_buf(&mut Cursor::new(obj), &load_options, |_| unreachable!())
+            .unwrap()
+            .0
+            .remove(0)
+            .mesh
+    };
+
+    // The polyline is neither triangulated nor ignored along with the two
+    // vertex face
+    let mesh = load(tobj::LoadOptions {
+        triangulate: true,
+        ..Default::default()
+    });
+    assert_eq!(mesh.indices, vec![0, 1, 2, 2, 3, 3]);
+    assert_eq!(mesh.line_indices, vec![0, 1, 2, 3, 0]);
+    assert_eq!(mesh.line_arities, vec![5]);
+    assert_eq!(mesh.line_texcoord_indices, vec![1, 0, 2, 0, 1]);
+
+    // With a single index the polyline shares vertices with the faces where
+    // they match
+    let mesh = load(tobj::LoadOptions {
+        single_index: true,
+        ignore_points: true,
+        ..Default::default()
+    });
+    assert_eq!(mesh.line_indices, vec![5, 6, 2, 7, 5]);
+    assert!(mesh.line_texcoord_indices.is_empty());
+
+    let mesh = load(tobj::LoadOptions {
+        ignore_lines: true,
+        ..Default::default()
+    });
+    assert!(mesh.line_indices.is_empty());
+    assert_eq!(mesh.face_arities, vec![3, 2]);
+
+    let err = tobj::load_obj_buf(
+        &mut Cursor::new("v 0 0 0\nl 1\n"),
+        &tobj::LoadOptions::default(),
+        |_| unreachable!(),
+    )
+    .unwrap_err();
+    assert_eq!(err, tobj::LoadErrorKind::FaceParseError);
+}
+
+#[test]
+fn test_points() {
+    let obj = "
+v 0 0 0
+v 1 0 0
+v 1 1 0
+v 0 1 0
+f 1 2 3
+p 4 -2
+f 1
+p 3
+";
+    let load = |load_options| {
+        tobj::load_obj_buf(&mut Cursor::new(obj), &load_options, |_| unreachable!())
+            .unwrap()
+            .0
+            .remove(0)
+            .mesh
+    };
+
+    // Points don't end up in the triangles
+    let mesh = load(tobj::GPU_LOAD_OPTIONS);
+    assert_eq!(mesh.indices, vec![0, 1, 2]);
+    assert!(mesh.point_indices.is_empty());
+
+    let mesh = load(tobj::LoadOptions {
+        single_index: true,
+        triangulate: true,
+        ..Default::default()
+    });
+    assert_eq!(mesh.indices, vec![0, 1, 2]);
+    assert_eq!(mesh.point_indices, vec![3, 2, 0, 2]);
+    assert_eq!(mesh.positions.len(), 3 * 4);
+    assert!(mesh.face_arities.is_empty());
+
+    let err = tobj::load_obj_buf(
+        &mut Cursor::new("v 0 0 0\np 1 2\n"),
+        &tobj::LoadOptions::default(),
+        |_| unreachable!(),
+    )
+    .unwrap_err();
+    assert_eq!(err, tobj::LoadErrorKind::FaceVertexOutOfBounds);
+    assert_eq!(err.line(), Some(2));
+}
+
+#[test]
+fn test_point_cloud() {
+    let obj = "
+o scan
+v 0 0 0 1 0 0
+v 1 0 0 0 1 0
+v 0 1 0 0 0 1
+vn 0 0 1
+vn 0 1 0
+vn 1 0 0
+";
+    let load_options = tobj::LoadOptions {
+        point_cloud: true,
+        ..Default::default()
+    };
+    let (models, _, _) =
+        tobj::load_obj_buf(&mut Cursor::new(obj), &load_options, |_| unreachable!()).unwrap();
+    assert_eq!(models.len(), 1);
+    assert_eq!(models[0].name, "scan");
+    let mesh = &models[0].mesh;
+    assert_eq!(mesh.point_indices, vec![0, 1, 2]);
+    assert_eq!(mesh.positions, vec![0., 0., 0., 1., 0., 0., 0., 1., 0.]);
+    assert_eq!(mesh.vertex_color, vec![1., 0., 0., 0., 1., 0., 0., 0., 1.]);
+    assert_eq!(mesh.normals, vec![0., 0., 1., 0., 1., 0., 1., 0., 0.]);
+    assert!(mesh.indices.is_empty());
+
+    // Without the option the vertices are dropped
+    let (models, _, _) = tobj::load_obj_buf(
+        &mut Cursor::new(obj),
+        &tobj::LoadOptions::default(),
+        |_| unreachable!(),
+    )
+    .unwrap();
+    assert_eq!(models.len(), 1);
+    assert!(models[0].mesh.positions.is_empty());
+
+    // Stray vertices of a file with faces become an additional model
+    let obj = "
+v 0 0 0
+v 1 0 0
+v 5 5 5
+v 1 1 0
+o tri
+g side
+f 1 2 4
+";
+    let (models, _, _) =
+        tobj::load_obj_buf(&mut Cursor::new(obj), &load_options, |_| unreachable!()).unwrap();
+    assert_eq!(models.len(), 2);
+    assert_eq!(models[0].mesh.indices, vec![0, 1, 2]);
+    // It doesn't take the name of the last model, which its vertices are not
+    // part of
+    assert_eq!(models[1].name, "unnamed_object");
+    assert_eq!(models[1].object, None);
+    assert!(models[1].groups.is_empty());
+    assert_eq!(models[1].mesh.positions, vec![5., 5., 5.]);
+    assert_eq!(models[1].mesh.point_indices, vec![0]);
+
+    let (raw, _, _) =
+        tobj::load_obj_buf_raw(&mut Cursor::new(obj), &load_options, |_| unreachable!()).unwrap();
+    assert_eq!(raw.shapes.len(), 2);
+    assert_eq!(raw.shapes[1].point_indices[0].v, 2);
+    assert_eq!(raw.shapes[1].name, "unnamed_object");
+}
+
+#[test]
+fn test_homogeneous_w() {
+    let obj = "
+v 0 0 0 2
+v 1 0 0
+v 1 1 0 1 0 0
+v 0 1 0
+vt 0 0 0.5
+vt 1 0
+vt 1 1
+f 1/1 2/2 3/3
+f 1/1 3/3 4/3
+cstype rat bezier
+deg 1
+curv 0 1 1 2
+end
+";
+    let load = |load_options| {
+        tobj::load_obj_buf(&mut Cursor::new(obj), &load_options, |_| unreachable!())
+            .unwrap()
+            .0
+            .remove(0)
+    };
+
+    let model = load(tobj::LoadOptions {
+        single_index: true,
+        preserve_w: true,
+        ..Default::default()
+    });
+    let mesh = &model.mesh;
+    assert_eq!(mesh.positions.len(), 4 * 3);
+    assert_eq!(mesh.position_w, vec![2., 1., 1., 1.]);
+    assert_eq!(mesh.texcoord_w, vec![0.5, 0., 0., 0.]);
+    // The w of the first position isn't mistaken for a color, and vertices
+    // without a color are white
+    assert_eq!(
+        mesh.vertex_color,
+        vec![1., 1., 1., 1., 1., 1., 1., 0., 0., 1., 1., 1.]
+    );
+    // Control points are weighted by w
+    assert_eq!(
+        model.free_form.curves[0].control_points[0],
+        [0., 0., 0., 2.]
+    );
+    assert_eq!(
+        model.free_form.curves[0].control_points[1],
+        [1., 0., 0., 1.]
+    );
+
+    let mesh = load(tobj::LoadOptions {
+        preserve_w: true,
+        ..Default::default()
+    })
+    .mesh;
+    assert_eq!(mesh.position_w, vec![2., 1., 1., 1.]);
+    assert_eq!(mesh.texcoord_w, vec![0.5, 0., 0.]);
+
+    let model = load(tobj::LoadOptions::default());
+    assert!(model.mesh.position_w.is_empty());
+    assert!(model.mesh.texcoord_w.is_empty());
+    assert_eq!(model.free_form.curves[0].control_points[0][3], 2.);
+}
+
+#[test]
+fn test_vertex_color_alpha() {
+    let obj = "
+v 0 0 0 1 0 0 0.5
+v 1 0 0 0 1 0
+v 1 1 0
+f 1 2 3
+";
+    let (models, _, _) = tobj::load_obj_buf(
+        &mut Cursor::new(obj),
+        &tobj::GPU_LOAD_OPTIONS,
+        |_| unreachable!(),
+    )
+    .unwrap();
+    let mesh = &models[0].mesh;
+    assert_eq!(mesh.vertex_color, vec![1., 0., 0., 0., 1., 0., 1., 1., 1.]);
+    assert_eq!(mesh.vertex_color_alpha, vec![0.5, 1., 1.]);
+
+    let mut out = Vec::new();
+    tobj::write_obj_buf(&mut out, &models, &[], None).unwrap();
+    let out = String::from_utf8(out).unwrap();
+    assert!(out.contains("v 0 0 0 1 0 0 0.5\n"));
+    assert!(out.contains("v 1 1 0 1 1 1 1\n"));
+}
+
+#[test]
+fn test_mrgb_vertex_color() {
+    let obj = "
+v 0 0 0
+v 1 0 0
+#MRGB ffff0000ff00ff00
+v 1 1 0
+#MRGB 000000ff
+f 1 2 3
+";
+    let (models, _, _) = tobj::load_obj_buf(
+        &mut Cursor::new(obj),
+        &tobj::GPU_LOAD_OPTIONS,
+        |_| unreachable!(),
+    )
+    .unwrap();
+    let mesh = &models[0].mesh;
+    assert_eq!(mesh.vertex_color, vec![1., 0., 0., 0., 1., 0., 0., 0., 1.]);
+    assert!(mesh.vertex_color_alpha.is_empty());
+}
+
+#[test]
+fn test_load_obj_bytes() {
+    let load_options = tobj::LoadOptions {
+        triangulate: true,
+        single_index: true,
+        ..Default::default()
+    };
+    let material_loader = |p: &std::path::Path| match p.to_str().unwrap() {
+        "cornell_box.mtl" => tobj::load_mtl_buf(&mut Cursor::new(CORNELL_BOX_MTL1)),
+        "cornell_box2.mtl" => tobj::load_mtl_buf(&mut Cursor::new(CORNELL_BOX_MTL2)),
+        _ => unreachable!(),
+    };
+    let (models, mats, _) =
+        tobj::load_obj_bytes(CORNELL_BOX_OBJ.as_bytes(), &load_options, material_loader).unwrap();
+    let (expected_models, expected_mats, _) = tobj::load_obj_buf(
+        &mut Cursor::new(CORNELL_BOX_OBJ),
+        &load_options,
+        material_loader,
+    )
+    .unwrap();
+    assert_eq!(format!("{:?}", models), format!("{:?}", expected_models));
+    assert_eq!(mats.unwrap(), expected_mats.unwrap());
+
+    // Lines that aren't valid UTF-8 are errors, unless they are skipped
+    let obj = b"v 0 0 0\r\nv 1 0 0\r\nv 0 1 0\r\no \xff\r\nf 1 2 3\r\n";
+    let err =
+        tobj::load_obj_bytes(obj, &tobj::LoadOptions::default(), |_| unreachable!()).unwrap_err();
+    assert_eq!(err, tobj::LoadErrorKind::ReadError);
+    assert_eq!(err.line(), Some(4));
+    let (models, _, warnings) = tobj::load_obj_bytes(
+        obj,
+        &tobj::LoadOptions {
+            skip_invalid_lines: true,
+            ..Default::default()
+        },
+        |_| unreachable!(),
+    )
+    .unwrap();
+    assert_eq!(models[0].mesh.indices, vec![0, 1, 2]);
+    assert_eq!(warnings.len(), 1);
+}
+
+// The numbers parsed from bytes are the same as those of the standard library
+#[test]
+fn test_parse_float_bytes() {
+    for word in [
+        "0",
+        "-0",
+        "1.",
+        ".5",
+        "+2.25",
+        "0.1",
+        "-123.456",
+        "16777217",
+        "0.30000000000000004",
+        "123456789.123456789",
+        "3.4028235e38",
+        "1e-3",
+        "-inf",
+        "nan",
+        "1..2",
+        "-",
+        "",
+    ] {
+        assert_eq!(
+            format!("{:?}", crate::parse_float_bytes(word.as_bytes())),
+            format!("{:?}", word.parse::<Float>().ok()),
+            "{}",
+            word
+        );
+    }
+}
+
+#[test]
+fn test_parse_index_bytes() {
+    for word in [
+        "0",
+        "-0",
+        "1",
+        "+1",
+        "-12",
+        "007",
+        "1.5",
+        "1e3",
+        "9223372036854775807",
+        "9223372036854775808",
+        "-9223372036854775808",
+        "-9223372036854775809",
+        "99999999999999999999",
+        "+",
+        "-",
+        "",
+    ] {
+        assert_eq!(
+            crate::parse_index_bytes(word.as_bytes()),
+            word.parse::<isize>().ok(),
+            "{}",
+            word
+        );
+    }
+}
+
+#[test]
+#[cfg(feature = "mmap")]
+fn test_load_obj_mmap() {
+    let (models, mats, _) =
+        unsafe { tobj::load_obj_mmap("obj/cornell_box.obj", &tobj::GPU_LOAD_OPTIONS) }.unwrap();
+    assert_eq!(models.len(), 8);
+    assert_eq!(mats.unwrap().len(), 5);
+}